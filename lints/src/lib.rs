@@ -0,0 +1,327 @@
+//! Checks that `catch!` expands without warnings, including pedantic clippy
+//! lints, in sync and async code, with and without a wildcard arm.
+#![deny(warnings, unused, clippy::all, clippy::pedantic)]
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::num::ParseIntError;
+use try_catch::{catch, throws, try_catch};
+
+#[derive(Debug)]
+pub struct Negative;
+
+impl fmt::Display for Negative {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("negative number")
+    }
+}
+
+impl Error for Negative {}
+
+/// Typed arms and a wildcard arm.
+#[must_use]
+pub fn parse(text: &str) -> i32 {
+    catch! {
+        try {
+            let number: i32 = text.parse()?;
+            if number < 0 {
+                throw Negative;
+            }
+            number
+        } catch _: Negative {
+            -1
+        } catch _: ParseIntError {
+            -2
+        } catch _ {
+            0
+        }
+    }
+}
+
+/// Typed arms only, leaving a `Result`.
+///
+/// # Errors
+/// Errors other than [`ParseIntError`].
+pub fn parse_typed(text: &str) -> Result<i32, Box<dyn Error>> {
+    catch! {
+        try {
+            text.parse::<i32>()?
+        } catch error: ParseIntError {
+            i32::from(error.to_string().is_empty())
+        } catch _: io::Error {
+            -1
+        }
+    }
+}
+
+/// Arms that only inspect the error, and a single expression after `try`.
+#[must_use]
+pub fn parse_logged(text: &str, log: &mut Vec<String>) -> i32 {
+    catch! {
+        try text.parse::<i32>()?;
+        catch &error: ParseIntError => log.push(error.to_string()),
+        catch _ => 0,
+    }
+}
+
+/// Control flow in the try block and the arms.
+#[must_use]
+pub fn sum(texts: &[&str]) -> i32 {
+    let mut total = 0;
+    for text in texts {
+        total += catch! {
+            try {
+                if text.is_empty() {
+                    break;
+                }
+                text.parse::<i32>()?
+            } catch _: ParseIntError {
+                continue;
+            } catch _ {
+                return -1;
+            }
+        };
+    }
+    total
+}
+
+/// An async try block, with typed arms only.
+///
+/// # Errors
+/// Errors other than [`ParseIntError`].
+pub async fn parse_async(text: &str) -> Result<i32, Box<dyn Error>> {
+    let parsed = async { text.parse::<i32>() };
+    catch! {
+        try {
+            parsed.await?
+        } catch _: ParseIntError {
+            0
+        }
+    }
+}
+
+/// An async try block that is retried, with a `catch panic` arm in sync code.
+pub async fn retried(text: &str) -> i32 {
+    let number = catch! {
+        try retry 2 {
+            async {}.await;
+            text.parse::<i32>()?
+        } catch _ {
+            0
+        }
+    };
+    number
+        + catch! {
+            try {
+                text.parse::<i32>()?
+            } catch panic _ {
+                -1
+            } catch _ {
+                0
+            }
+        }
+}
+
+macro_rules! parse {
+    ($text:expr) => {
+        $text.parse::<i32>()?
+    };
+}
+
+/// Blocks around a single expression, which the expansion must not report as
+/// unnecessary braces, in inline, closure and async try blocks.
+///
+/// # Errors
+/// Errors other than [`ParseIntError`].
+pub async fn braces(text: &str) -> Result<i32, Box<dyn Error>> {
+    let inline = catch! {
+        try { text.parse::<i32>()? }
+        catch _: ParseIntError { 1 }
+        catch _ { 2 }
+    };
+    let moved = catch! {
+        move try { text.parse::<i32>()? }
+        catch _: ParseIntError { 1 }
+    }?;
+    let opaque = catch! {
+        try { parse!(text) }
+        catch _: ParseIntError { 1 }
+    }?;
+    let awaited = catch! {
+        try { async { 1 }.await + text.parse::<i32>()? }
+        catch _ { 2 }
+    };
+    Ok(inline + moved + opaque + awaited)
+}
+
+/// A try block that never completes, run in place.
+#[must_use]
+pub fn diverging(code: i32) -> i32 {
+    catch! {
+        try {
+            std::process::exit(code)
+        } catch _ {
+            0
+        }
+    }
+}
+
+/// A try block that never completes, run in a closure and under `catch panic`.
+#[must_use]
+pub fn diverging_retried(code: i32) -> i32 {
+    let retried = catch! {
+        try retry 2 {
+            panic!("exit code {}", code)
+        } catch _ {
+            0
+        }
+    };
+    retried
+        + catch! {
+            try {
+                panic!("exit code {}", code)
+            } catch panic _ {
+                1
+            } catch _ {
+                0
+            }
+        }
+}
+
+/// A try block that never completes, run as a future.
+pub async fn diverging_async(code: i32) -> i32 {
+    catch! {
+        try {
+            async {}.await;
+            std::process::exit(code)
+        } catch _ {
+            0
+        }
+    }
+}
+
+/// A function whose body is the try block.
+#[must_use]
+#[try_catch(catch _: ParseIntError => -1, catch _ => 0)]
+pub fn parse_attribute(text: &str) -> i32 {
+    let number: i32 = text.parse()?;
+    if number < 0 {
+        Err(Negative)?;
+    }
+    number
+}
+
+/// A value to fall back on.
+pub struct Fallback(pub i32);
+
+impl Fallback {
+    /// An async method whose body is the try block, with typed arms only.
+    ///
+    /// # Errors
+    /// Errors other than [`ParseIntError`].
+    #[try_catch(catch _: ParseIntError => self.0)]
+    pub async fn parse(&self, text: &str) -> Result<i32, Box<dyn Error>> {
+        async {}.await;
+        text.parse::<i32>()?
+    }
+}
+
+/// A main function exiting with the code of the arm that handled the error.
+#[catch::main(catch _: ParseIntError => 2, catch _: Negative => 3)]
+pub fn run(text: &str) {
+    if parse_attribute(text) < 0 {
+        Err(Negative)?;
+    }
+    text.parse::<u8>()?;
+}
+
+/// Numbers parsed in the given radix.
+pub struct Radix(pub u32);
+
+/// Methods sharing the arms of their impl block.
+#[try_catch(catch _: ParseIntError => -1, catch _ => 0)]
+impl Radix {
+    #[try_catch(skip)]
+    #[must_use]
+    pub fn decimal() -> Self {
+        Radix(10)
+    }
+
+    #[must_use]
+    pub fn parse(&self, text: &str) -> i32 {
+        i32::from_str_radix(text, self.0)?
+    }
+
+    pub async fn parse_positive(&self, text: &str) -> i32 {
+        async {}.await;
+        let number = self.parse(text);
+        if number < 0 {
+            Err(Negative)?;
+        }
+        number
+    }
+}
+
+/// A function returning a generated enum of its errors.
+///
+/// # Errors
+///
+/// When `text` is not a positive number.
+#[throws(enum ParseError { Parse(ParseIntError), Negative(Negative) })]
+pub fn parse_declared(text: &str) -> i32 {
+    let number: i32 = text.parse()?;
+    if number < 0 {
+        Err(Negative)?;
+    }
+    number
+}
+
+/// Deferred statements, unconditional and on errors only.
+///
+/// # Errors
+///
+/// When `text` is not a number.
+pub fn parse_deferred(text: &str, log: &std::cell::Cell<u32>) -> Result<i32, ParseIntError> {
+    try_catch::defer! { log.set(log.get() + 1); }
+    let failed = try_catch::defer_on_error! { log.set(0); };
+    let number = text.parse()?;
+    failed.defuse();
+    Ok(number)
+}
+
+/// An expression that stays a `Result` despite its wildcard arm.
+///
+/// # Errors
+///
+/// When `text` is not a number.
+pub fn parse_result(text: &str) -> Result<i32, Box<dyn Error>> {
+    let number = try_catch::catch_expr! {
+        try {
+            text.parse::<i32>()?
+        }
+        catch error: ParseIntError {
+            rethrow;
+        }
+        catch _ => 0,
+    }?;
+    Ok(number)
+}
+
+/// A stream skipping the first ten items that are not numbers, and replacing
+/// the others with `-1`.
+pub fn parse_stream<S>(texts: S) -> impl futures_core::Stream<Item = Result<i32, Box<dyn Error>>>
+where
+    S: futures_core::Stream<Item = Result<i32, ParseIntError>>,
+{
+    let mut skipped = 0_u32;
+    try_catch::catch_stream! {
+        match texts {
+            catch _: ParseIntError if skipped < 10 => {
+                skipped += 1;
+                continue;
+            }
+            catch _: ParseIntError => -1,
+        }
+    }
+}
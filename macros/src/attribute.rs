@@ -0,0 +1,213 @@
+//! The attributes that run the body of a function as the try block of a
+//! `catch!`: `#[try_catch(..)]`, with the arms given to the attribute, and
+//! `#[catch::main]` and `#[catch::test]`.
+
+use crate::kw;
+use crate::parse::{Catch, TryCatch};
+use crate::prelude::*;
+use crate::template::template;
+
+use proc_macro2::{Span, TokenTree};
+use quote::ToTokens;
+use syn::parse::{ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+
+/// The keywords that start the clauses following the try block, where the
+/// markers written before `try` end.
+const CLAUSES: &[&str] = &[
+    "else",
+    "context",
+    "cleanup",
+    "on_cancel",
+    "catch",
+    "finally",
+];
+
+/// Wraps a function, or each method of an impl block, in the arms of `attr`.
+pub fn try_catch(attr: TokenStream2, item: TokenStream2) -> Result<TokenStream2> {
+    if attr.is_empty() {
+        return Err(Error::new(
+            Span::call_site(),
+            "expected the catch arms, as in `#[try_catch(catch e: io::Error => ..)]`",
+        ));
+    }
+    if is_skip(attr.clone()) {
+        return Err(Error::new(
+            attr.span(),
+            "`skip` opts a method out of the arms of `#[try_catch(..)]` on its impl block, \
+             it cannot be used elsewhere",
+        ));
+    }
+    match syn::parse2(item)? {
+        Item::Fn(mut function) => {
+            function.block = Box::new(wrap(attr, &function.block)?);
+            Ok(function.into_token_stream())
+        }
+        Item::Impl(mut block) => {
+            let mut methods = Vec::new();
+            for item in &mut block.items {
+                // Associated consts and types are left as they are, as are
+                // the bodyless functions that syn parses as verbatim items.
+                let method = match item {
+                    ImplItem::Method(method) => method,
+                    _ => continue,
+                };
+                match method.attrs.iter().position(is_try_catch) {
+                    Some(index) if is_skip(method.attrs[index].parse_args()?) => {
+                        method.attrs.remove(index);
+                    }
+                    // Methods with arms of their own are wrapped by them alone.
+                    Some(_) => {}
+                    None => methods.push(method),
+                }
+            }
+            let mut error = None;
+            for method in methods {
+                match wrap(attr.clone(), &method.block) {
+                    Ok(wrapped) => method.block = wrapped,
+                    Err(arms) => {
+                        error = Some(arms.to_compile_error());
+                        break;
+                    }
+                }
+            }
+            // The `skip` attributes are gone even if the arms are invalid, so
+            // that they are not reported as well.
+            Ok(quote![#error #block])
+        }
+        item => Err(Error::new(
+            item.span(),
+            "`#[try_catch(..)]` applies to functions and impl blocks",
+        )),
+    }
+}
+
+fn wrap(attr: TokenStream2, block: &Block) -> Result<Block> {
+    let try_catch = parse(attr, block.to_token_stream())?;
+    let body = template(try_catch);
+    Ok(parse_quote!({ #body }))
+}
+
+fn is_try_catch(attr: &Attribute) -> bool {
+    attr.path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "try_catch")
+}
+
+/// Whether `tokens`, the arguments of `#[try_catch]`, are `skip`.
+fn is_skip(tokens: TokenStream2) -> bool {
+    syn::parse2::<Ident>(tokens).is_ok_and(|ident| ident == "skip")
+}
+
+/// Runs `main` as a try block whose arms give the exit code of the process.
+/// Errors that no arm handles are reported on stderr, with exit code 1.
+pub fn main(attr: TokenStream2, item: TokenStream2) -> Result<TokenStream2> {
+    let mut function: ItemFn = syn::parse2(item)?;
+    returns_unit(
+        &function,
+        "`#[catch::main]` functions return `()`, the arms give the exit code instead",
+    )?;
+    let block = &function.block;
+    let mut try_catch = parse(attr, quote![{ let () = #block; 0 }])?;
+    report_unhandled(&mut try_catch, quote![exit_report]);
+    let body = template(try_catch);
+    let code = Ident::new("__try_catch_code", Span::mixed_site());
+    function.block = Box::new(parse_quote!({
+        let #code: i32 = #body;
+        if #code != 0 {
+            ::std::process::exit(#code);
+        }
+    }));
+    Ok(function.into_token_stream())
+}
+
+/// Runs a test as a try block, panicking with the report of the errors that no
+/// arm handles. With `expect = Type`, the test fails unless it raises a `Type`.
+pub fn test(attr: TokenStream2, item: TokenStream2) -> Result<TokenStream2> {
+    let mut function: ItemFn = syn::parse2(item)?;
+    returns_unit(
+        &function,
+        "`#[catch::test]` functions return `()`, errors fail the test instead",
+    )?;
+    let (expected, attr) = parse_expected.parse2(attr)?;
+    let block = &function.block;
+    let mut try_catch = match &expected {
+        Some(types) => {
+            let names = types.iter();
+            let block = quote![{
+                let () = #block;
+                ::try_catch::__private::unexpected_success(&[#(::core::any::type_name::<#names>()),*])
+            }];
+            let mut try_catch = parse(attr, block)?;
+            try_catch
+                .catches
+                .insert(0, parse_quote![catch _: #types => {}]);
+            try_catch
+        }
+        None => parse(attr, block.to_token_stream())?,
+    };
+    report_unhandled(&mut try_catch, quote![panic_report]);
+    let body = template(try_catch);
+    function.block = Box::new(parse_quote!({ #body }));
+    Ok(function.into_token_stream())
+}
+
+/// Parses the `expect = Type | ..` that may start the arguments of
+/// `#[catch::test]`, returning the rest of them.
+fn parse_expected(
+    input: ParseStream,
+) -> Result<(Option<Punctuated<Type, Token![|]>>, TokenStream2)> {
+    let mut expected = None;
+    if input.peek(kw::expect) && input.peek2(Token![=]) {
+        input.parse::<kw::expect>()?;
+        input.parse::<Token![=]>()?;
+        expected = Some(Punctuated::parse_separated_nonempty(input)?);
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+    }
+    Ok((expected, input.parse()?))
+}
+
+fn returns_unit(function: &ItemFn, message: &str) -> Result<()> {
+    match &function.sig.output {
+        ReturnType::Type(_, ty) => Err(Error::new(ty.span(), message)),
+        ReturnType::Default => Ok(()),
+    }
+}
+
+/// Adds a wildcard arm passing the errors that no arm handles to `report`,
+/// one of the reporting functions of `try_catch::__private`.
+fn report_unhandled(try_catch: &mut TryCatch, report: TokenStream2) {
+    if !try_catch.catches.iter().any(TryCatch::is_wildcard) {
+        let error = Ident::new("__try_catch_error", Span::mixed_site());
+        let catch: Catch = parse_quote![
+            catch #error => ::try_catch::__private::#report(&*#error)
+        ];
+        try_catch.catches.push(catch);
+    }
+}
+
+/// Parses the try block `block` with the markers and clauses of `attr`, as in
+/// `propagate catch e: io::Error => ..`.
+fn parse(attr: TokenStream2, block: TokenStream2) -> Result<TryCatch> {
+    let mut markers = TokenStream2::new();
+    let mut tokens = attr.into_iter();
+    let mut clauses = TokenStream2::new();
+    for token in tokens.by_ref() {
+        match &token {
+            TokenTree::Ident(ident) if CLAUSES.iter().any(|clause| ident == clause) => {
+                clauses.extend([token]);
+                break;
+            }
+            _ => markers.extend([token]),
+        }
+    }
+    clauses.extend(tokens);
+    let try_kw = <Token![try]>::default();
+    let try_catch: TryCatch = syn::parse2(quote![#markers #try_kw #block #clauses])?;
+    try_catch.check_resume()?;
+    Ok(try_catch)
+}
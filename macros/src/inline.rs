@@ -0,0 +1,434 @@
+use crate::prelude::*;
+
+use proc_macro2::{Span, TokenTree};
+use quote::{quote_spanned, ToTokens};
+use syn::{
+    parse_quote_spanned,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit_mut::{self, VisitMut},
+};
+
+/// Macros of the standard library, which expand to code without a `return` or
+/// a `?` of its own, so they can be looked into like any other expression.
+const STD_MACROS: &[&str] = &[
+    "assert",
+    "assert_eq",
+    "assert_ne",
+    "column",
+    "concat",
+    "dbg",
+    "debug_assert",
+    "debug_assert_eq",
+    "debug_assert_ne",
+    "env",
+    "eprint",
+    "eprintln",
+    "file",
+    "format",
+    "format_args",
+    "include_bytes",
+    "include_str",
+    "line",
+    "matches",
+    "module_path",
+    "option_env",
+    "panic",
+    "print",
+    "println",
+    "stringify",
+    "todo",
+    "unimplemented",
+    "unreachable",
+    "vec",
+    "write",
+    "writeln",
+];
+
+/// Macros raising an error with `return Err(..)`, such as `throw!` and
+/// `bail!`, which raise it from the try block instead when it runs in place.
+const RAISING_MACROS: &[&str] = &["bail", "ensure", "throw"];
+
+/// Runs the try block `value`, such as `Ok({ .. })`, in place instead of in a
+/// closure, so that `return`, `break` and `continue` behave as if the block
+/// was written without the macro.
+///
+/// The block becomes a labeled block: each `?` breaks out of it with the error,
+/// and unlabeled `break`s and `continue`s for a loop around the macro break out
+/// of it too, and are repeated once outside. Fails with the reason the block
+/// cannot run in place if it calls a macro that may return or use `?` itself,
+/// since those would then leave the enclosing function instead of the try
+/// block.
+pub fn inline(value: TokenStream2) -> std::result::Result<TokenStream2, String> {
+    let mut value: Expr =
+        syn::parse2(value).map_err(|_| "its statements could not be looked into".to_string())?;
+    let mut inliner = Inliner {
+        label: Lifetime::new("'__try_catch_try", Span::mixed_site()),
+        flow: Ident::new("__TryCatchFlow", Span::mixed_site()),
+        loops: 0,
+        opaque: None,
+        breaks: false,
+        breaks_with_value: false,
+        continues: false,
+    };
+    inliner.visit_expr_mut(&mut value);
+    if let Some(name) = inliner.opaque {
+        return Err(format!("it calls `{}!`", name));
+    }
+    let Inliner { label, flow, .. } = &inliner;
+    let flow_value = Ident::new("__try_catch_flow", Span::mixed_site());
+    let mut variants = quote![Done(T),];
+    let mut arms = quote![#flow::Done(#flow_value) => #flow_value,];
+    if inliner.breaks {
+        variants.extend(quote![Break(B),]);
+        arms.extend(if inliner.breaks_with_value {
+            quote![#flow::Break(#flow_value) => break #flow_value,]
+        } else {
+            quote![#flow::Break(_) => break,]
+        });
+    }
+    if inliner.continues {
+        variants.extend(quote![Continue,]);
+        arms.extend(quote![#flow::Continue => continue,]);
+    }
+    let generics = if inliner.breaks {
+        quote![<T, B>]
+    } else {
+        quote![<T>]
+    };
+    let result = Ident::new("__try_catch_inline", Span::mixed_site());
+    // The block may always leave with `return`, leaving `Done` unreachable.
+    Ok(quote![{
+        enum #flow #generics { #variants }
+        #[allow(unreachable_code)]
+        let #result = match #label: { #flow::Done(#value) } {
+            #arms
+        };
+        #result
+    }])
+}
+
+struct Inliner {
+    label: Lifetime,
+    flow: Ident,
+    /// The number of loops of the try block around the current expression.
+    loops: usize,
+    /// The first macro the block calls that cannot be looked into.
+    opaque: Option<String>,
+    breaks: bool,
+    breaks_with_value: bool,
+    continues: bool,
+}
+
+impl Inliner {
+    fn in_loop(&mut self, body: &mut Block) {
+        self.loops += 1;
+        self.visit_block_mut(body);
+        self.loops -= 1;
+    }
+
+    /// Raises the error of `mac`, one of [`RAISING_MACROS`], from the try
+    /// block: the macro returns it from a closure, and it leaves the block as
+    /// a `?` would.
+    fn raise(&self, mac: &Macro) -> Expr {
+        let (label, flow) = (&self.label, &self.flow);
+        let result = Ident::new("__try_catch_raised", Span::mixed_site());
+        let error = Ident::new("__try_catch_error", Span::mixed_site());
+        parse_quote_spanned![Span::mixed_site()=> {
+            #[allow(unreachable_code)]
+            let #result = (|| {
+                #mac;
+                ::core::result::Result::Ok(())
+            })();
+            if let ::core::result::Result::Err(#error) = #result {
+                break #label #flow::Done(::core::result::Result::Err(#error));
+            }
+        }]
+    }
+}
+
+/// Whether `mac` is one of [`RAISING_MACROS`].
+fn is_raising(mac: &Macro) -> bool {
+    mac.path
+        .segments
+        .last()
+        .is_some_and(|segment| RAISING_MACROS.iter().any(|name| segment.ident == name))
+}
+
+impl VisitMut for Inliner {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        let (label, flow) = (self.label.clone(), self.flow.clone());
+        match expr {
+            // These have a `?`, `break` and `continue` of their own.
+            Expr::Closure(_) | Expr::Async(_) | Expr::TryBlock(_) => {}
+            Expr::Try(ExprTry {
+                expr: value,
+                question_token,
+                ..
+            }) => {
+                self.visit_expr_mut(value);
+                let ok = Ident::new("__try_catch_ok", Span::mixed_site());
+                let error = Ident::new("__try_catch_error", Span::mixed_site());
+                // Conversion errors are reported at the `?`, like `?` does.
+                let from = quote_spanned![question_token.span()=>
+                    ::core::convert::From::from(#error)
+                ];
+                let span = Span::mixed_site().located_at(question_token.span());
+                // A `?` converting an error into its own type is not linted.
+                *expr = parse_quote_spanned![span=>
+                    (match #value {
+                        ::core::result::Result::Ok(#ok) => #ok,
+                        #[allow(clippy::useless_conversion)]
+                        ::core::result::Result::Err(#error) => {
+                            break #label #flow::Done(::core::result::Result::Err(#from))
+                        }
+                    })
+                ];
+            }
+            Expr::Break(ExprBreak {
+                label: None,
+                expr: value,
+                ..
+            }) if self.loops == 0 => {
+                self.breaks = true;
+                *expr = match value {
+                    Some(value) => {
+                        self.breaks_with_value = true;
+                        self.visit_expr_mut(value);
+                        parse_quote_spanned![Span::mixed_site()=> break #label #flow::Break(#value)]
+                    }
+                    None => {
+                        parse_quote_spanned![Span::mixed_site()=> break #label #flow::Break(())]
+                    }
+                };
+            }
+            Expr::Continue(ExprContinue { label: None, .. }) if self.loops == 0 => {
+                self.continues = true;
+                *expr = parse_quote_spanned![Span::mixed_site()=> break #label #flow::Continue];
+            }
+            Expr::ForLoop(for_loop) => {
+                self.visit_expr_mut(&mut for_loop.expr);
+                self.in_loop(&mut for_loop.body);
+            }
+            Expr::While(while_loop) => {
+                self.visit_expr_mut(&mut while_loop.cond);
+                self.in_loop(&mut while_loop.body);
+            }
+            Expr::Loop(loop_expr) => self.in_loop(&mut loop_expr.body),
+            Expr::Macro(ExprMacro { mac, .. }) if is_raising(mac) => *expr = self.raise(mac),
+            _ => visit_mut::visit_expr_mut(self, expr),
+        }
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        if let Stmt::Item(Item::Macro(ItemMacro {
+            ident: None, mac, ..
+        })) = stmt
+        {
+            if is_raising(mac) {
+                *stmt = Stmt::Expr(self.raise(mac));
+                return;
+            }
+        }
+        visit_mut::visit_stmt_mut(self, stmt);
+    }
+
+    fn visit_item_mut(&mut self, item: &mut Item) {
+        // Macros in statement position, other items are not part of the block.
+        if let Item::Macro(ItemMacro {
+            ident: None, mac, ..
+        }) = item
+        {
+            self.visit_macro_mut(mac);
+        }
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut Macro) {
+        let name = match mac.path.segments.last() {
+            Some(segment) => segment.ident.to_string(),
+            None => return,
+        };
+        if !STD_MACROS.contains(&&*name) {
+            self.opaque.get_or_insert(name);
+            return;
+        }
+        if !has_control_flow(mac.tokens.clone()) {
+            return;
+        }
+        match mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            Ok(mut args) => {
+                for arg in args.iter_mut() {
+                    self.visit_expr_mut(arg);
+                }
+                mac.tokens = args.into_token_stream();
+            }
+            Err(_) => {
+                self.opaque.get_or_insert(name);
+            }
+        }
+    }
+}
+
+/// Whether `tokens` contain a `?`, `break` or `continue`.
+fn has_control_flow(tokens: TokenStream2) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Punct(punct) => punct.as_char() == '?',
+        TokenTree::Ident(ident) => ident == "break" || ident == "continue",
+        TokenTree::Group(group) => has_control_flow(group.stream()),
+        TokenTree::Literal(_) => false,
+    })
+}
+
+/// Finds the first `return`, `break` or `continue` of `tokens` that leaves
+/// them, and the first `?` if `question` is set, with the keyword it found.
+/// Labels starting with `'__try_catch` are those of the expansion, such as
+/// the block that `rethrow` breaks out of, which stays in the expression.
+pub fn find_exit(tokens: TokenStream2, question: bool) -> Option<(Span, &'static str)> {
+    let mut finder = ExitFinder {
+        question,
+        loops: 0,
+        labels: vec![],
+        found: None,
+    };
+    match syn::parse2::<Expr>(tokens.clone()) {
+        Ok(mut expr) => finder.visit_expr_mut(&mut expr),
+        Err(_) => finder.visit_tokens(tokens),
+    }
+    finder.found
+}
+
+struct ExitFinder {
+    question: bool,
+    /// The number of loops around the current expression.
+    loops: usize,
+    /// The labels of the loops and blocks around the current expression.
+    labels: Vec<String>,
+    found: Option<(Span, &'static str)>,
+}
+
+impl ExitFinder {
+    fn leaves(&self, label: Option<&Lifetime>) -> bool {
+        match label {
+            Some(label) => {
+                let name = label.ident.to_string();
+                !name.starts_with("__try_catch") && !self.labels.contains(&name)
+            }
+            None => self.loops == 0,
+        }
+    }
+
+    /// Visits `body`, the body of a loop if `is_loop` is set, or else a block,
+    /// which `label` may label.
+    fn visit_body(&mut self, label: &Option<Label>, is_loop: bool, body: &mut Block) {
+        self.loops += usize::from(is_loop);
+        if let Some(label) = label {
+            self.labels.push(label.name.ident.to_string());
+        }
+        self.visit_block_mut(body);
+        if label.is_some() {
+            self.labels.pop();
+        }
+        self.loops -= usize::from(is_loop);
+    }
+
+    /// Looks through the tokens of a macro that cannot be parsed as
+    /// expressions, where a `return`, `break` or `continue` is taken to leave
+    /// them unless it is in the braces of a loop.
+    fn visit_tokens(&mut self, tokens: TokenStream2) {
+        let mut in_loop = false;
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            if self.found.is_some() {
+                return;
+            }
+            match token {
+                TokenTree::Ident(ident) if ident == "return" => {
+                    self.found = Some((ident.span(), "return"));
+                }
+                TokenTree::Ident(ident) if ident == "break" || ident == "continue" => {
+                    let labeled = matches!(tokens.peek(), Some(TokenTree::Punct(tick)) if tick.as_char() == '\'');
+                    if labeled || self.loops == 0 {
+                        let keyword = if ident == "break" {
+                            "break"
+                        } else {
+                            "continue"
+                        };
+                        self.found = Some((ident.span(), keyword));
+                    }
+                }
+                TokenTree::Ident(ident) => {
+                    in_loop |= ident == "loop" || ident == "while" || ident == "for";
+                }
+                TokenTree::Punct(punct) if punct.as_char() == '?' && self.question => {
+                    self.found = Some((punct.span(), "?"));
+                }
+                TokenTree::Group(group) => {
+                    let body = in_loop && group.delimiter() == proc_macro2::Delimiter::Brace;
+                    self.loops += usize::from(body);
+                    self.visit_tokens(group.stream());
+                    self.loops -= usize::from(body);
+                    in_loop &= !body;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl VisitMut for ExitFinder {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if self.found.is_some() {
+            return;
+        }
+        match expr {
+            // These have a `return`, `?`, `break` and `continue` of their own.
+            Expr::Closure(_) | Expr::Async(_) | Expr::TryBlock(_) => {}
+            Expr::Return(ret) => self.found = Some((ret.return_token.span, "return")),
+            Expr::Try(ExprTry { question_token, .. }) if self.question => {
+                self.found = Some((question_token.span, "?"));
+            }
+            Expr::Break(ExprBreak {
+                break_token, label, ..
+            }) if self.leaves(label.as_ref()) => self.found = Some((break_token.span, "break")),
+            Expr::Continue(ExprContinue {
+                continue_token,
+                label,
+                ..
+            }) if self.leaves(label.as_ref()) => {
+                self.found = Some((continue_token.span, "continue"));
+            }
+            Expr::ForLoop(for_loop) => {
+                self.visit_expr_mut(&mut for_loop.expr);
+                self.visit_body(&for_loop.label, true, &mut for_loop.body);
+            }
+            Expr::While(while_loop) => {
+                self.visit_expr_mut(&mut while_loop.cond);
+                self.visit_body(&while_loop.label, true, &mut while_loop.body);
+            }
+            Expr::Loop(loop_expr) => self.visit_body(&loop_expr.label, true, &mut loop_expr.body),
+            Expr::Block(block) => self.visit_body(&block.label, false, &mut block.block),
+            _ => visit_mut::visit_expr_mut(self, expr),
+        }
+    }
+
+    fn visit_item_mut(&mut self, item: &mut Item) {
+        // Macros in statement position, other items are not part of the block.
+        if let Item::Macro(ItemMacro {
+            ident: None, mac, ..
+        }) = item
+        {
+            self.visit_macro_mut(mac);
+        }
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut Macro) {
+        match mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            Ok(mut args) => {
+                for arg in args.iter_mut() {
+                    self.visit_expr_mut(arg);
+                }
+            }
+            Err(_) => self.visit_tokens(mac.tokens.clone()),
+        }
+    }
+}
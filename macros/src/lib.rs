@@ -0,0 +1,142 @@
+//! Procedural macros for the [`try-catch`](https://docs.rs/try-catch) crate.
+//! Depend on `try-catch` instead of using this crate directly.
+
+mod attribute;
+mod inline;
+mod parse;
+mod prelude;
+mod stream;
+mod template;
+#[cfg(test)]
+mod tests;
+mod throws;
+
+use crate::parse::TryCatch;
+use crate::prelude::*;
+use crate::template::template;
+
+mod kw {
+    syn::custom_keyword!(catch);
+    syn::custom_keyword!(finally);
+    syn::custom_keyword!(retry);
+    syn::custom_keyword!(with);
+    syn::custom_keyword!(sleep);
+    syn::custom_keyword!(or);
+    syn::custom_keyword!(context);
+    syn::custom_keyword!(cleanup);
+    syn::custom_keyword!(on_cancel);
+    syn::custom_keyword!(timeout);
+    syn::custom_keyword!(sync);
+    syn::custom_keyword!(lazy);
+    syn::custom_keyword!(located);
+    syn::custom_keyword!(traced);
+    syn::custom_keyword!(map_err);
+    syn::custom_keyword!(exhaustive);
+    syn::custom_keyword!(strict);
+    syn::custom_keyword!(no_wildcard);
+    syn::custom_keyword!(propagate);
+    syn::custom_keyword!(expect);
+    syn::custom_keyword!(or_default);
+    syn::custom_keyword!(into);
+    syn::custom_keyword!(error);
+    syn::custom_keyword!(send);
+    syn::custom_keyword!(flatten);
+    syn::custom_keyword!(deep);
+    syn::custom_keyword!(panic);
+    syn::custom_keyword!(none);
+}
+
+#[proc_macro]
+pub fn catch(input: TokenStream) -> TokenStream {
+    let try_catch = match syn::parse::<TryCatch>(input) {
+        Ok(try_catch) => try_catch,
+        Err(error) => return compile_error(error),
+    };
+    if let Err(error) = try_catch.check_resume() {
+        return compile_error(error);
+    }
+    template(try_catch).into()
+}
+
+#[proc_macro]
+pub fn catch_opt(input: TokenStream) -> TokenStream {
+    let try_catch = match syn::parse::<TryCatch>(input) {
+        Ok(try_catch) => try_catch,
+        Err(error) => return compile_error(error),
+    };
+    match try_catch.into_optional() {
+        Ok(try_catch) => template(try_catch).into(),
+        Err(error) => compile_error(error),
+    }
+}
+
+#[proc_macro]
+pub fn catch_expr(input: TokenStream) -> TokenStream {
+    let try_catch = match syn::parse::<TryCatch>(input) {
+        Ok(try_catch) => try_catch,
+        Err(error) => return compile_error(error),
+    };
+    if let Err(error) = try_catch.check_resume() {
+        return compile_error(error);
+    }
+    match try_catch.into_result("catch_expr!") {
+        Ok(try_catch) => template(try_catch).into(),
+        Err(error) => compile_error(error),
+    }
+}
+
+#[proc_macro]
+pub fn catch_stream(input: TokenStream) -> TokenStream {
+    let try_catch = match syn::parse::<TryCatch>(input) {
+        Ok(try_catch) => try_catch,
+        Err(error) => return compile_error(error),
+    };
+    match stream::catch_stream(try_catch) {
+        Ok(expanded) => expanded.into(),
+        Err(error) => compile_error(error),
+    }
+}
+
+#[proc_macro_attribute]
+pub fn try_catch(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_attribute(attribute::try_catch, attr, item)
+}
+
+#[proc_macro_attribute]
+pub fn throws(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_attribute(throws::throws, attr, item)
+}
+
+#[proc_macro_attribute]
+pub fn main(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_attribute(attribute::main, attr, item)
+}
+
+#[proc_macro_attribute]
+pub fn test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_attribute(attribute::test, attr, item)
+}
+
+fn expand_attribute(
+    expand: fn(TokenStream2, TokenStream2) -> Result<TokenStream2>,
+    attr: TokenStream,
+    item: TokenStream,
+) -> TokenStream {
+    let item = TokenStream2::from(item);
+    match expand(attr.into(), item.clone()) {
+        Ok(expanded) => expanded.into(),
+        Err(error) => {
+            // The item is kept, so that its uses are not reported as well.
+            let errors = error.to_compile_error();
+            quote![#errors #item].into()
+        }
+    }
+}
+
+/// Reports `error` in a block, since an error pointing at several places
+/// expands to several `compile_error!`s, and only one expression can stand
+/// where the macro was invoked.
+fn compile_error(error: Error) -> TokenStream {
+    let errors = error.to_compile_error();
+    quote![{ #errors }].into()
+}
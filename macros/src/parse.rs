@@ -0,0 +1,2010 @@
+use crate::kw;
+use crate::prelude::*;
+
+use proc_macro2::{Delimiter, Spacing, Span, TokenTree};
+use quote::ToTokens;
+use syn::{
+    parse::{discouraged::Speculative, Parse, Parser},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit_mut::{self, VisitMut},
+};
+
+pub struct TryCatch {
+    pub capture_move: bool,
+    pub lazy: bool,
+    pub unmatched: Unmatched,
+    /// The error type that the arms translate errors into, as in `into AppError`.
+    pub into: Option<Type>,
+    /// The type carrying errors to the arms in place of `Box<dyn Error>`, as in
+    /// `error = anyhow::Error`.
+    pub carrier: Option<Type>,
+    pub error_enum: Option<ErrorEnum>,
+    pub source: Source,
+    pub else_clause: Option<Else>,
+    pub context: Option<Expr>,
+    /// Wraps the errors that no arm handles in a `Located` error, with the
+    /// location of the invocation, as in `located try { .. }`.
+    pub located: Option<Span>,
+    /// Wraps the errors that reach an untyped arm or that no arm handles in a
+    /// `Traced` error, with a backtrace, as in `traced try { .. }`.
+    pub traced: Option<Span>,
+    pub cleanup: Option<ExprBlock>,
+    /// Runs when the future awaiting an async try block is dropped before the
+    /// block completes, as in `on_cancel { .. }`.
+    pub on_cancel: Option<ExprBlock>,
+    pub catches: Vec<Catch>,
+    pub finally: Option<ExprBlock>,
+    pub is_async: bool,
+    /// Warnings found while parsing, emitted with the expansion.
+    pub warnings: Vec<(Span, String)>,
+}
+/// What happens to errors that no catch arm handles.
+pub enum Unmatched {
+    /// The expression evaluates to a `Result` holding them.
+    Return,
+    /// They are returned from the enclosing function, as with `?`.
+    Propagate(Span),
+    /// They cause a panic with the given message, as in `expect("..")`.
+    Panic(LitStr),
+    /// They are replaced by the default value of the success type.
+    Default,
+    /// The expression evaluates to a `Result` holding them, even when the arms
+    /// leave none, as in `catch_expr!`.
+    Result,
+}
+/// Where the `Result` handled by the catch arms comes from.
+pub enum Source {
+    Try(Box<TryBlock>),
+    /// An existing `Result`, as in `match result { catch .. }`.
+    Result(Box<Expr>),
+}
+/// A single error type whose variants are matched directly, as in
+/// `enum MyError try { .. } catch MyError::NotFound { .. }`.
+pub struct ErrorEnum {
+    pub ty: Type,
+    pub catches: Vec<VariantCatch>,
+}
+pub struct TryBlock {
+    pub retry: Option<Retry>,
+    pub timeout: Option<Timeout>,
+    pub resources: Vec<Resource>,
+    pub attrs: Vec<Attribute>,
+    /// The type of the value of the block, as in `try -> Vec<u8> { .. }`.
+    pub ty: Option<Type>,
+    /// Whether the value of the block is a `Result` whose error is caught, as
+    /// if it ended with `?`.
+    pub flatten: bool,
+    /// Converts the error of each `?` in the block before it is carried to
+    /// the arms, as in `map_err = OwnedError::from`.
+    pub map_err: Option<Expr>,
+    pub block: ExprBlock,
+    pub fallbacks: Vec<Fallback>,
+}
+pub enum Retry {
+    Count(u32),
+    Policy {
+        policy: Box<Expr>,
+        sleep: Option<Box<Expr>>,
+    },
+}
+pub struct Timeout {
+    pub duration: Expr,
+    pub sleep: Option<Box<Expr>>,
+}
+pub struct Resource {
+    pat: Pat,
+    ty: Option<Type>,
+    init: Expr,
+}
+pub struct Fallback {
+    pub error: Option<Ident>,
+    pub block: ExprBlock,
+}
+pub struct Else {
+    pub value: Ident,
+    pub block: ExprBlock,
+}
+pub struct Catch {
+    pub attrs: Vec<Attribute>,
+    /// The `catch` keyword, where diagnostics about the whole arm point.
+    pub catch_kw: Ident,
+    /// `None` when the arm is a handler, as in `catch io::Error => handler`.
+    pub error: Option<Pat>,
+    /// Set by `catch &e: Type` and `catch mut e: Type`, which inspect or modify the
+    /// error and leave it to the next arms.
+    pub by_ref: Option<ByRef>,
+    /// Whether the arm looks for its type through the `source()` chain, as in
+    /// `catch deep e: Type`.
+    pub deep: bool,
+    /// Whether the arm catches panics, as in `catch panic payload { .. }`.
+    pub panic: bool,
+    /// Empty for the wildcard arm, and several types for `catch e: A | B`.
+    pub err_types: Vec<ErrType>,
+    /// The condition in `catch e: Type if condition { .. }`.
+    pub guard: Option<Expr>,
+    pub retry: Option<u32>,
+    /// Whether the body uses `rethrow`, so that it has to be wrapped in a block
+    /// labeled with [`arm_label`].
+    pub rethrows: bool,
+    /// The first `resume` in the body, which only `catch_opt!` supports.
+    pub resume: Option<Span>,
+    pub body: Expr,
+}
+#[derive(Clone, Copy, PartialEq)]
+pub enum ByRef {
+    Shared,
+    Mut,
+}
+pub struct ErrType {
+    pub ty: Type,
+    /// The `io::ErrorKind`s in `catch e: io::Error(NotFound | PermissionDenied)`.
+    pub kinds: Vec<Path>,
+}
+pub struct VariantCatch {
+    pub attrs: Vec<Attribute>,
+    pub pat: Pat,
+    pub guard: Option<Expr>,
+    pub body: Expr,
+}
+
+fn parse_block(input: &parse::ParseStream) -> Result<ExprBlock> {
+    let mut expr = input.parse()?;
+    // A `$block:block` passed on by `macro_rules!` arrives in an invisible group.
+    while let Expr::Group(group) = expr {
+        expr = *group.expr;
+    }
+    match expr {
+        Expr::Block(block) => Ok(block),
+        span => Err(Error::new(span.span(), "Expected a block `{ /* ... */ }`.")),
+    }
+}
+
+/// Parses a block that runs as the try block, where `throw` can be used.
+fn parse_try_block(input: &parse::ParseStream) -> Result<ExprBlock> {
+    if !input.peek(token::Brace) {
+        return parse_block(input);
+    }
+    let block: proc_macro2::Group = input.parse()?;
+    syn::parse2(rewrite_throw(block.to_token_stream())?)
+}
+
+/// Parses the body of `try`, which is either a block or a single expression
+/// terminated by `;`, as in `try do_thing(x)?; catch ...`.
+fn parse_try_body(input: &parse::ParseStream) -> Result<ExprBlock> {
+    if input.peek(token::Brace) {
+        return parse_try_block(input);
+    }
+    if input.is_empty() || input.peek(kw::catch) {
+        return Err(input.error("expected a block after `try`, as in `try { .. } catch e { .. }`"));
+    }
+    let expr = input.step(|cursor| {
+        let mut rest = *cursor;
+        let mut expr = TokenStream2::new();
+        while let Some((token, next)) = rest.token_tree() {
+            match &token {
+                TokenTree::Punct(semi) if semi.as_char() == ';' => {
+                    if expr.is_empty() {
+                        return Err(Error::new(
+                            semi.span(),
+                            "expected an expression or a block after `try`",
+                        ));
+                    }
+                    return Ok((expr, next));
+                }
+                _ => expr.extend(Some(token)),
+            }
+            rest = next;
+        }
+        Err(cursor.error("expected `;` after the expression of `try`"))
+    })?;
+    let block = quote::quote_spanned!(Span::mixed_site()=> { #expr });
+    syn::parse2(rewrite_throw(block)?)
+}
+
+fn parse_retry(input: &parse::ParseStream) -> Result<Option<u32>> {
+    if !input.peek(kw::retry) {
+        return Ok(None);
+    }
+    let _retry_kw: kw::retry = input.parse()?;
+    parse_retry_count(input).map(Some)
+}
+
+fn parse_retry_count(input: &parse::ParseStream) -> Result<u32> {
+    let count: LitInt = input.parse()?;
+    let retries = count.base10_parse()?;
+    if retries == 0 {
+        return Err(Error::new(
+            count.span(),
+            "`retry 0` never retries, remove the `retry` clause",
+        ));
+    }
+    Ok(retries)
+}
+
+impl Parse for Retry {
+    fn parse(input: parse::ParseStream) -> Result<Self> {
+        let _retry_kw: kw::retry = input.parse()?;
+        if !input.peek(kw::with) {
+            return parse_retry_count(&input).map(Retry::Count);
+        }
+        let _with_kw: kw::with = input.parse()?;
+        let policy = Box::new(Expr::parse_without_eager_brace(input)?);
+        let sleep = parse_sleep(&input)?;
+        Ok(Retry::Policy { policy, sleep })
+    }
+}
+
+impl Parse for Timeout {
+    fn parse(input: parse::ParseStream) -> Result<Self> {
+        let _timeout_kw: kw::timeout = input.parse()?;
+        let duration = Expr::parse_without_eager_brace(input)?;
+        let sleep = parse_sleep(&input)?;
+        Ok(Timeout { duration, sleep })
+    }
+}
+
+fn parse_sleep(input: &parse::ParseStream) -> Result<Option<Box<Expr>>> {
+    if !input.peek(kw::sleep) {
+        return Ok(None);
+    }
+    let _sleep_kw: kw::sleep = input.parse()?;
+    Ok(Some(Box::new(Expr::parse_without_eager_brace(input)?)))
+}
+
+/// The function used to wait in async code when no `sleep` was given.
+pub fn default_sleep() -> Option<TokenStream2> {
+    if cfg!(feature = "tokio") {
+        // Reached through `try_catch`, which the crate invoking the macro may
+        // depend on without depending on `tokio` itself.
+        Some(quote![::try_catch::__private::tokio::time::sleep])
+    } else {
+        None
+    }
+}
+
+impl Parse for TryCatch {
+    fn parse(input: parse::ParseStream) -> Result<Self> {
+        let mut try_attrs = input.call(Attribute::parse_outer)?;
+        let mut capture_move = false;
+        let mut lazy = false;
+        let mut unmatched = Unmatched::Return;
+        let mut into = None;
+        let mut carrier: Option<Type> = None;
+        let mut flatten = None;
+        let mut located = None;
+        let mut traced = None;
+        let mut map_err = None;
+        let mut error_enum = None;
+        let mut checks = Checks::default();
+        loop {
+            if input.peek(Token![move]) {
+                let _move_kw: Token![move] = input.parse()?;
+                capture_move = true;
+            } else if input.peek(kw::flatten) {
+                flatten = Some(input.parse::<kw::flatten>()?);
+            } else if input.peek(kw::lazy) {
+                let _lazy_kw: kw::lazy = input.parse()?;
+                lazy = true;
+            } else if input.peek(kw::located) {
+                located = Some(input.parse::<kw::located>()?.span);
+            } else if input.peek(kw::traced) {
+                traced = Some(input.parse::<kw::traced>()?.span);
+            } else if input.peek(kw::map_err) {
+                let _map_err_kw: kw::map_err = input.parse()?;
+                let _eq: Token![=] = input.parse()?;
+                map_err = Some(Expr::parse_without_eager_brace(input)?);
+            } else if input.peek(kw::propagate)
+                || input.peek(kw::expect)
+                || input.peek(kw::or_default)
+            {
+                if !matches!(unmatched, Unmatched::Return) {
+                    return Err(input
+                        .error("only one of `propagate`, `expect` and `or_default` can be used"));
+                }
+                if input.peek(kw::propagate) {
+                    let propagate_kw: kw::propagate = input.parse()?;
+                    unmatched = Unmatched::Propagate(propagate_kw.span);
+                } else if input.peek(kw::or_default) {
+                    let _or_default_kw: kw::or_default = input.parse()?;
+                    unmatched = Unmatched::Default;
+                } else {
+                    let _expect_kw: kw::expect = input.parse()?;
+                    let content;
+                    parenthesized!(content in input);
+                    unmatched = Unmatched::Panic(content.parse()?);
+                }
+            } else if input.peek(kw::into) {
+                let _into_kw: kw::into = input.parse()?;
+                into = Some(input.parse()?);
+            } else if input.peek(kw::send) || input.peek(kw::error) && input.peek2(Token![=]) {
+                if carrier.is_some() {
+                    return Err(input.error("only one of `send` and `error = ..` can be used"));
+                }
+                if input.peek(kw::send) {
+                    let _send_kw: kw::send = input.parse()?;
+                    carrier = Some(parse_quote![
+                        ::try_catch::__private::Box<
+                            dyn ::core::error::Error + ::core::marker::Send + ::core::marker::Sync,
+                        >
+                    ]);
+                } else {
+                    let _error_kw: kw::error = input.parse()?;
+                    let _eq: Token![=] = input.parse()?;
+                    carrier = Some(input.parse()?);
+                }
+            } else if input.peek(Token![enum]) {
+                let _enum_kw: Token![enum] = input.parse()?;
+                error_enum = Some(ErrorEnum {
+                    ty: input.parse()?,
+                    catches: vec![],
+                });
+            } else if input.peek(kw::exhaustive) {
+                let exhaustive_kw: kw::exhaustive = input.parse()?;
+                let content;
+                parenthesized!(content in input);
+                let types = Punctuated::<Type, Token![,]>::parse_terminated(&content)?;
+                checks.exhaustive = Some((exhaustive_kw, types.into_iter().collect()));
+            } else if input.peek(kw::strict) {
+                checks.strict = Some(input.parse()?);
+            } else if input.peek(kw::no_wildcard) {
+                checks.no_wildcard = Some(input.parse()?);
+            } else {
+                break;
+            }
+        }
+        if let (Unmatched::Propagate(span), true) = (&unmatched, lazy) {
+            return Err(Error::new(
+                *span,
+                "`propagate` cannot return from the enclosing function of a `lazy` block",
+            ));
+        }
+        if let (false, Some(error_enum)) = (matches!(unmatched, Unmatched::Return), &error_enum) {
+            return Err(Error::new(
+                error_enum.ty.span(),
+                "`enum` arms handle every error, so no error is left unmatched",
+            ));
+        }
+        if let (Some(carrier), Some(error_enum)) = (&carrier, &error_enum) {
+            return Err(Error::new(
+                carrier.span(),
+                format!(
+                    "errors are carried as `{}` with `enum`, they cannot also be carried as `{}`",
+                    error_enum.ty.to_token_stream(),
+                    carrier.to_token_stream(),
+                ),
+            ));
+        }
+        if input.peek(Token![match]) {
+            if let Some(flatten) = flatten {
+                return Err(Error::new(
+                    flatten.span,
+                    "`flatten` can only be used with a try block",
+                ));
+            }
+            if let Some(map_err) = map_err {
+                return Err(Error::new(
+                    map_err.span(),
+                    "`map_err` can only be used with a try block",
+                ));
+            }
+            if let Some(attr) = try_attrs.first() {
+                return Err(Error::new(
+                    attr.span(),
+                    "attributes are only supported on try blocks",
+                ));
+            }
+            let _match_kw: Token![match] = input.parse()?;
+            let result = Box::new(Expr::parse_without_eager_brace(input)?);
+            let content;
+            braced!(content in input);
+            let mut try_catch = TryCatch {
+                capture_move,
+                lazy,
+                unmatched,
+                into,
+                carrier,
+                error_enum,
+                source: Source::Result(result),
+                else_clause: None,
+                context: None,
+                located,
+                traced,
+                cleanup: None,
+                on_cancel: None,
+                catches: vec![],
+                finally: None,
+                is_async: lazy,
+                warnings: vec![],
+            };
+            try_catch.parse_clauses(&content)?;
+            try_catch.check(checks)?;
+            if let Some(catch) = try_catch.catches.iter().find(|catch| catch.retry.is_some()) {
+                return Err(Error::new(
+                    catch.body.span(),
+                    "`retry` can only be used with a try block",
+                ));
+            }
+            return Ok(try_catch);
+        }
+        let _try_kw: Token![try] = input.parse()?;
+        let retry = if input.peek(kw::retry) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        let timeout: Option<Timeout> = if input.peek(kw::timeout) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        let mut resources = vec![];
+        if input.peek(kw::with) {
+            let _with_kw: kw::with = input.parse()?;
+            loop {
+                resources.push(input.parse()?);
+                if !input.peek(Token![,]) {
+                    break;
+                }
+                let _comma: Token![,] = input.parse()?;
+            }
+        }
+        try_attrs.extend(input.call(Attribute::parse_outer)?);
+        let asyncness = if input.peek(Token![async]) {
+            let _async_kw: Token![async] = input.parse()?;
+            Some(true)
+        } else if input.peek(kw::sync) {
+            let _sync_kw: kw::sync = input.parse()?;
+            Some(false)
+        } else {
+            None
+        };
+        let ty = if input.peek(Token![->]) {
+            let _arrow: Token![->] = input.parse()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        let try_block = parse_try_body(&input)?;
+        let mut fallbacks: Vec<Fallback> = vec![];
+        while input.peek(kw::or) {
+            fallbacks.push(input.parse()?);
+        }
+        if let (true, Some(false)) = (lazy, asyncness) {
+            return Err(input.error("`lazy` try blocks are always async"));
+        }
+        let is_async = lazy
+            || asyncness.unwrap_or_else(|| {
+                let mut ts = try_block.to_token_stream();
+                ts.extend(resources.iter().map(ToTokens::to_token_stream));
+                ts.extend(
+                    fallbacks
+                        .iter()
+                        .map(|fallback| fallback.block.to_token_stream()),
+                );
+                is_async(ts)
+            });
+        if let Some(Retry::Policy {
+            policy,
+            sleep: None,
+        }) = &retry
+        {
+            if is_async && default_sleep().is_none() {
+                return Err(Error::new(
+                    policy.span(),
+                    "retrying an async try block needs a sleep function, \
+                     e.g. `retry with policy sleep tokio::time::sleep`, \
+                     or the `tokio` feature",
+                ));
+            }
+        }
+        if let (Some(error_enum), Some(Retry::Policy { policy, .. })) = (&error_enum, &retry) {
+            return Err(Error::new(
+                policy.span(),
+                format!(
+                    "`retry with` needs boxed errors, it cannot be used with `enum {}`",
+                    error_enum.ty.to_token_stream()
+                ),
+            ));
+        }
+        if let Some(Timeout { duration, sleep }) = &timeout {
+            if error_enum.is_some() {
+                return Err(Error::new(
+                    duration.span(),
+                    "`timeout` needs boxed errors, it cannot be used with `enum`",
+                ));
+            }
+            if !is_async {
+                return Err(Error::new(
+                    duration.span(),
+                    "`timeout` can only be used with async try blocks",
+                ));
+            }
+            if sleep.is_none() && default_sleep().is_none() {
+                return Err(Error::new(
+                    duration.span(),
+                    "`timeout` needs a sleep function, \
+                     e.g. `timeout duration sleep tokio::time::sleep`, \
+                     or the `tokio` feature",
+                ));
+            }
+        }
+
+        let mut try_catch = TryCatch {
+            capture_move,
+            lazy,
+            unmatched,
+            into,
+            carrier,
+            error_enum,
+            source: Source::Try(Box::new(TryBlock {
+                retry,
+                timeout,
+                resources,
+                attrs: try_attrs,
+                ty,
+                flatten: flatten.is_some(),
+                map_err,
+                block: try_block,
+                fallbacks,
+            })),
+            else_clause: None,
+            context: None,
+            located,
+            traced,
+            cleanup: None,
+            on_cancel: None,
+            catches: vec![],
+            finally: None,
+            is_async,
+            warnings: vec![],
+        };
+        try_catch.parse_clauses(input)?;
+        try_catch.check(checks)?;
+        Ok(try_catch)
+    }
+}
+
+/// Compile time checks on the catch arms, requested before `try`.
+#[derive(Default)]
+struct Checks {
+    exhaustive: Option<(kw::exhaustive, Vec<Type>)>,
+    strict: Option<kw::strict>,
+    no_wildcard: Option<kw::no_wildcard>,
+}
+
+impl TryCatch {
+    /// Rejects `resume` outside of `catch_opt!`.
+    pub fn check_resume(&self) -> Result<()> {
+        match self.catches.iter().find_map(|catch| catch.resume) {
+            Some(span) => Err(Error::new(
+                span,
+                "`resume` can only be used in `catch_opt!`; \
+                 the value of a `catch!` arm is its body",
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Turns a parsed invocation into one for `catch_expr!`, which evaluates to
+    /// a `Result` whether or not the arms handle every error, with the values
+    /// of the arms in `Ok`. `catch_stream!` evaluates its arms the same way.
+    pub fn into_result(mut self, name: &str) -> Result<Self> {
+        if let Some(error_enum) = &self.error_enum {
+            return Err(Error::new(
+                error_enum.ty.span(),
+                format!("`enum` cannot be used with `{}`", name),
+            ));
+        }
+        if !matches!(self.unmatched, Unmatched::Return) {
+            return Err(Error::new(
+                Span::call_site(),
+                format!(
+                    "`{}` always evaluates to the `Result`, \
+                     so `propagate`, `expect` and `or_default` cannot be used",
+                    name
+                ),
+            ));
+        }
+        self.unmatched = Unmatched::Result;
+        Ok(self)
+    }
+
+    /// Turns a parsed invocation into one for `catch_opt!`, which evaluates to
+    /// `Some` on success and `None` on errors, unless the arm that handles them
+    /// resumes with a value using `resume value;`.
+    pub fn into_optional(mut self) -> Result<Self> {
+        if let Some(error_enum) = &self.error_enum {
+            return Err(Error::new(
+                error_enum.ty.span(),
+                "`enum` cannot be used with `catch_opt!`",
+            ));
+        }
+        if let Some(into) = &self.into {
+            return Err(Error::new(
+                into.span(),
+                "`into` cannot be used with `catch_opt!`",
+            ));
+        }
+        if !matches!(self.unmatched, Unmatched::Return) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`catch_opt!` already turns unmatched errors into `None`, \
+                 so `propagate`, `expect` and `or_default` cannot be used",
+            ));
+        }
+        self.unmatched = Unmatched::Default;
+
+        let value = Ident::new("__try_catch_value", Span::mixed_site());
+        let block = match self.else_clause.take() {
+            Some(Else { value: name, block }) => quote![{
+                let #name = #value;
+                let #value = #block;
+                ::core::option::Option::Some(#value)
+            }],
+            None => quote![{ ::core::option::Option::Some(#value) }],
+        };
+        self.else_clause = Some(Else {
+            value: value.clone(),
+            block: parse2(block)?,
+        });
+
+        let label = resume_label();
+        for catch in &mut self.catches {
+            // Handler arms return the `Option` themselves, and arms catching by
+            // reference leave the error to the next arms.
+            if catch.error.is_none() || catch.by_ref.is_some() {
+                continue;
+            }
+            let body = &catch.body;
+            catch.body = parse2(quote![{
+                #[allow(unreachable_code)]
+                let #value = #label: {
+                    let _ = #body;
+                    ::core::option::Option::None
+                };
+                #value
+            }])?;
+        }
+        Ok(self)
+    }
+
+    fn check(&self, checks: Checks) -> Result<()> {
+        if let (Some(strict), Some(_)) = (&checks.strict, &checks.no_wildcard) {
+            return Err(Error::new(
+                strict.span,
+                "`strict` and `no_wildcard` cannot be used together",
+            ));
+        }
+        if let Some(strict) = checks.strict {
+            self.check_strict(strict)?;
+        }
+        if let Some(into) = &self.into {
+            self.check_into(into)?;
+        }
+        if self.carrier.as_ref().map_or(true, is_box) {
+            self.check_arm_types()?;
+        }
+        if let Some(catch) = self.catches.iter().find(|catch| catch.panic) {
+            if self.is_async {
+                return Err(Error::new(
+                    catch.body.span(),
+                    "`catch panic` cannot catch the panics of an async try block, \
+                     which happen when the future is polled",
+                ));
+            }
+            if let Source::Result(_) = self.source {
+                return Err(Error::new(
+                    catch.body.span(),
+                    "`catch panic` can only be used with a try block",
+                ));
+            }
+        }
+        if let Unmatched::Panic(_) = self.unmatched {
+            if let Some(catch) = self.catches.iter().find(|catch| Self::is_wildcard(catch)) {
+                return Err(Error::new(
+                    catch.body.span(),
+                    "a wildcard arm handles every error, so `expect` would never panic; \
+                     remove one of them",
+                ));
+            }
+        }
+        if checks.no_wildcard.is_some() {
+            // Arms catching by reference leave the error to the next arms,
+            // so they do not swallow anything.
+            let untyped = self
+                .catches
+                .iter()
+                .find(|catch| catch.err_types.is_empty() && catch.by_ref.is_none());
+            if let Some(catch) = untyped {
+                return Err(Error::new(
+                    catch.body.span(),
+                    "`no_wildcard` forbids untyped catch arms, which swallow unknown errors; \
+                     list the error types to catch, or remove `no_wildcard`",
+                ));
+            }
+        }
+        self.check_exhaustive(checks.exhaustive)
+    }
+
+    /// Whether `catch` handles every error, making the expression infallible.
+    pub fn is_wildcard(catch: &Catch) -> bool {
+        catch.err_types.is_empty() && catch.guard.is_none() && catch.by_ref.is_none()
+    }
+
+    /// Checks that the arms of an `into` invocation can translate every error.
+    fn check_into(&self, into: &Type) -> Result<()> {
+        if self.error_enum.is_some() || !matches!(self.unmatched, Unmatched::Return) {
+            return Err(Error::new(
+                into.span(),
+                "`into` cannot be used with `enum`, `propagate`, `expect` or `or_default`",
+            ));
+        }
+        if let Some(context) = &self.context {
+            return Err(Error::new(
+                context.span(),
+                "`context` needs boxed errors, it cannot be used with `into`",
+            ));
+        }
+        if let Some(located) = self.located {
+            return Err(Error::new(
+                located,
+                "`located` needs boxed errors, it cannot be used with `into`",
+            ));
+        }
+        if let Some(traced) = self.traced {
+            return Err(Error::new(
+                traced,
+                "`traced` needs boxed errors, it cannot be used with `into`",
+            ));
+        }
+        if let Some(catch) = self.catches.iter().find(|catch| catch.rethrows) {
+            return Err(Error::new(
+                catch.body.span(),
+                "`rethrow` cannot be used with `into`, the arm should return the error instead",
+            ));
+        }
+        if self.catches.iter().any(Self::is_wildcard) {
+            return Ok(());
+        }
+        let span = match self.catches.last() {
+            Some(catch) => catch.body.span(),
+            None => into.span(),
+        };
+        Err(Error::new(
+            span,
+            "`into` requires a wildcard arm such as `catch error { .. }` \
+             so that every error is translated",
+        ))
+    }
+
+    /// Checks that a `strict` invocation has a wildcard arm.
+    fn check_strict(&self, strict: kw::strict) -> Result<()> {
+        if self.error_enum.is_some() || self.catches.iter().any(Self::is_wildcard) {
+            return Ok(());
+        }
+        let span = match self.catches.last() {
+            Some(catch) => catch.body.span(),
+            None => strict.span,
+        };
+        Err(Error::new(
+            span,
+            "`strict` requires a wildcard arm such as `catch error { .. }` \
+             so that every error is handled; add one or remove `strict`",
+        ))
+    }
+
+    /// Rejects arm types that errors carried in a `Box<dyn Error>` can never be
+    /// downcast to, with an explanation of what to write instead.
+    fn check_arm_types(&self) -> Result<()> {
+        for err_type in self.catches.iter().flat_map(|catch| &catch.err_types) {
+            let ty = &err_type.ty;
+            if is_box(ty) {
+                return Err(Error::new_spanned(
+                    ty,
+                    "errors reach the arms already boxed, so a `Box` cannot be caught; \
+                     catch the error types it holds, or every error with `catch error { .. }`",
+                ));
+            }
+            let name = type_name(ty);
+            if ["anyhow::Error", "eyre::Report"]
+                .iter()
+                .any(|report| name.ends_with(report))
+            {
+                return Err(Error::new_spanned(
+                    ty,
+                    format!(
+                        "`{}` does not implement `std::error::Error`, so it cannot be caught; \
+                         catch the error types it wraps, or carry errors as `{}` with `error = {}`",
+                        name, name, name,
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that no arm catches a type that an earlier arm always catches,
+    /// which is an error when both name it the same way, and a warning when
+    /// one path ends with the other, as `io::Error` and `std::io::Error` do.
+    fn check_duplicates(&mut self) -> Result<()> {
+        let catches_all = |catch: &Catch| {
+            catch.guard.is_none()
+                && catch.by_ref.is_none()
+                && !catch.deep
+                && catch.error.as_ref().map_or(true, is_binding)
+        };
+        // `retry` arms still retry the errors that an earlier arm catches.
+        let shadowable = |catch: &Catch| !catch.deep && catch.retry.is_none();
+        for (i, catch) in self
+            .catches
+            .iter()
+            .enumerate()
+            .filter(|(_, catch)| shadowable(catch))
+        {
+            for err_type in &catch.err_types {
+                let earlier = self.catches[..i]
+                    .iter()
+                    .filter(|earlier| catches_all(earlier))
+                    .flat_map(|earlier| &earlier.err_types)
+                    .filter(|earlier| earlier.kinds.is_empty());
+                for shadowing in earlier {
+                    let (ty, other) = (&err_type.ty, &shadowing.ty);
+                    if ty.to_token_stream().to_string() == other.to_token_stream().to_string() {
+                        let mut error = Error::new(
+                            ty.span(),
+                            format!(
+                                "this arm never runs for `{}`, which an earlier arm already catches",
+                                type_name(ty),
+                            ),
+                        );
+                        error.combine(Error::new(other.span(), "the earlier arm is here"));
+                        return Err(error);
+                    }
+                    if same_path_suffix(ty, other) {
+                        self.warnings.push((
+                            ty.span(),
+                            format!(
+                                "this arm never runs if `{}` is the same type as `{}`, \
+                                 which an earlier arm already catches",
+                                type_name(ty),
+                                type_name(other),
+                            ),
+                        ));
+                        self.warnings.push((
+                            other.span(),
+                            format!("the earlier arm catching `{}` is here", type_name(other)),
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the typed arms handle exactly the types listed in
+    /// `exhaustive(..)`. Arms that may let an error through, because they
+    /// have a guard, filter kinds or only inspect it, do not count.
+    fn check_exhaustive(&self, exhaustive: Option<(kw::exhaustive, Vec<Type>)>) -> Result<()> {
+        let (exhaustive_kw, types) = match exhaustive {
+            Some(exhaustive) => exhaustive,
+            None => return Ok(()),
+        };
+        if self.error_enum.is_some() {
+            return Err(Error::new(
+                exhaustive_kw.span,
+                "`exhaustive` cannot be used with `enum`, whose arms are already checked by rustc",
+            ));
+        }
+        let key = |ty: &Type| ty.to_token_stream().to_string();
+        let declared: Vec<String> = types.iter().map(key).collect();
+        let mut handled = vec![];
+        for catch in &self.catches {
+            for err_type in &catch.err_types {
+                if !declared.contains(&key(&err_type.ty)) {
+                    return Err(Error::new(
+                        err_type.ty.span(),
+                        "this type is not listed in `exhaustive(..)`",
+                    ));
+                }
+                if catch.guard.is_none() && catch.by_ref.is_none() && err_type.kinds.is_empty() {
+                    handled.push(key(&err_type.ty));
+                }
+            }
+        }
+        let unhandled: Vec<&Type> = types
+            .iter()
+            .filter(|ty| !handled.contains(&key(ty)))
+            .collect();
+        if let Some(first) = unhandled.first() {
+            let names: Vec<String> = unhandled
+                .iter()
+                .map(|ty| format!("`{}`", ty.to_token_stream()))
+                .collect();
+            return Err(Error::new(
+                first.span(),
+                format!("unhandled error types: {}", names.join(", ")),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parses everything that follows the source of the errors: the `else`,
+    /// `context`, `cleanup` and `on_cancel` clauses, the catch arms and `finally`.
+    fn parse_clauses(&mut self, input: parse::ParseStream) -> Result<()> {
+        if input.peek(Token![else]) {
+            self.else_clause = Some(input.parse()?);
+        }
+        if input.peek(kw::context) {
+            let _context_kw: kw::context = input.parse()?;
+            self.context = Some(Expr::parse_without_eager_brace(input)?);
+        }
+        if input.peek(kw::cleanup) {
+            let _cleanup_kw: kw::cleanup = input.parse()?;
+            self.cleanup = Some(parse_block(&input)?);
+        }
+        if input.peek(kw::on_cancel) {
+            let on_cancel_kw: kw::on_cancel = input.parse()?;
+            if !self.is_async || matches!(self.source, Source::Result(_)) {
+                return Err(Error::new(
+                    on_cancel_kw.span,
+                    "`on_cancel` can only be used with async try blocks",
+                ));
+            }
+            self.on_cancel = Some(parse_block(&input)?);
+        }
+        if let (Some(carrier), Some(context)) = (&self.carrier, &self.context) {
+            return Err(Error::new(
+                context.span(),
+                format!(
+                    "`context` needs errors carried as `Box<dyn Error>`, it cannot be used with `{}`",
+                    carrier.to_token_stream(),
+                ),
+            ));
+        }
+        if let (Some(_), Some(located)) = (&self.carrier, self.located) {
+            return Err(Error::new(
+                located,
+                "`located` needs errors carried as `Box<dyn Error>`, \
+                 it cannot be used with `send` or `error = ..`",
+            ));
+        }
+        if let (Some(carrier), Some(traced)) = (&self.carrier, self.traced) {
+            if !is_box(carrier) {
+                return Err(Error::new(
+                    traced,
+                    format!(
+                        "`traced` needs errors carried in a box, it cannot be used with `{}`",
+                        carrier.to_token_stream(),
+                    ),
+                ));
+            }
+        }
+        if let Some(error_enum) = &mut self.error_enum {
+            if let Some(context) = &self.context {
+                return Err(Error::new(
+                    context.span(),
+                    "`context` needs boxed errors, it cannot be used with `enum`",
+                ));
+            }
+            if let Some(located) = self.located {
+                return Err(Error::new(
+                    located,
+                    "`located` needs boxed errors, it cannot be used with `enum`",
+                ));
+            }
+            if let Some(traced) = self.traced {
+                return Err(Error::new(
+                    traced,
+                    "`traced` needs boxed errors, it cannot be used with `enum`",
+                ));
+            }
+            while !input.is_empty() && !input.peek(kw::finally) {
+                error_enum.catches.push(input.parse()?);
+            }
+        }
+        while !input.is_empty() && !input.peek(kw::finally) {
+            self.catches.push(input.parse()?);
+        }
+        for catch in self.catches.iter().filter(|catch| Self::is_wildcard(catch)) {
+            if let Some(cfg) = catch.attrs.iter().find(|attr| attr.path.is_ident("cfg")) {
+                return Err(Error::new(
+                    cfg.span(),
+                    "`#[cfg]` is not supported on the wildcard arm, since it decides the type of the expression",
+                ));
+            }
+        }
+        if let Some(wildcard) = self.catches.iter().position(Self::is_wildcard) {
+            if let Some(shadowed) = self.catches.get(wildcard + 1) {
+                let mut error = Error::new(
+                    shadowed.catch_kw.span(),
+                    "this arm never runs, since the wildcard arm before it catches every error; \
+                     arms are tried in order, so the wildcard arm has to be the last one",
+                );
+                error.combine(Error::new(
+                    self.catches[wildcard].catch_kw.span(),
+                    "the wildcard arm is here",
+                ));
+                return Err(error);
+            }
+        }
+        self.check_duplicates()?;
+        if input.peek(kw::finally) {
+            let finally_kw: kw::finally = input.parse()?;
+            self.finally = Some(parse_block(&input)?);
+            if !input.is_empty() {
+                return Err(Error::new(
+                    finally_kw.span,
+                    "`finally` must be the last clause, after the catch arms",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Parse for Catch {
+    fn parse(input: parse::ParseStream) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let catch_kw = parse_catch_kw(input)?;
+        // `deep` followed by a name or pattern, not an error named `deep`.
+        let deep = if input.peek(kw::deep)
+            && (input.peek2(Ident) || input.peek2(Token![_]) || input.peek2(token::Paren))
+        {
+            Some(input.parse::<kw::deep>()?)
+        } else {
+            None
+        };
+        let panic = if deep.is_none()
+            && input.peek(kw::panic)
+            && (input.peek2(Ident) || input.peek2(Token![_]))
+        {
+            Some(input.parse::<kw::panic>()?)
+        } else {
+            None
+        };
+        // `none` followed by the body, not an error named `none`.
+        let none = if deep.is_none()
+            && panic.is_none()
+            && input.peek(kw::none)
+            && (input.peek2(token::Brace) || input.peek2(Token![=>]) || input.peek2(Token![if]))
+        {
+            Some(input.parse::<kw::none>()?)
+        } else {
+            None
+        };
+        let mut by_ref = None;
+        if input.peek(Token![&]) {
+            let _ref: Token![&] = input.parse()?;
+            by_ref = Some(ByRef::Shared);
+        }
+        if input.peek(Token![mut]) {
+            let _mut_kw: Token![mut] = input.parse()?;
+            by_ref = Some(ByRef::Mut);
+        }
+        if none.is_none()
+            && (input.is_empty() || input.peek(token::Brace) || input.peek(Token![=>]))
+        {
+            return Err(Error::new(
+                catch_kw.span(),
+                "expected the error after `catch`, as in `catch e { .. }` or `catch _ => ..`",
+            ));
+        }
+        if input.peek(Token![:]) {
+            return Err(
+                input.error("expected a name for the error before `:`, as in `catch e: Type`")
+            );
+        }
+        if by_ref.is_some() && !input.peek(Ident) && !input.peek(Token![_]) {
+            return Err(
+                input.error("expected a name, as in `catch &e: Type` or `catch mut e: Type`")
+            );
+        }
+        let pattern = if panic.is_some() || none.is_some() {
+            None
+        } else {
+            parse_pattern(input)?
+        };
+        if panic.is_none()
+            && none.is_none()
+            && pattern.is_none()
+            && !input.peek(token::Paren)
+            && !starts_with_binding(input)
+        {
+            let err_types = parse_err_types(input)?;
+            if !input.peek(Token![=>]) {
+                return Err(Error::new(
+                    err_types[0].ty.span(),
+                    "a catch arm without a binding needs a handler, e.g. `catch io::Error => handler`",
+                ));
+            }
+            if let Some(deep) = deep {
+                return Err(Error::new(
+                    deep.span,
+                    "`deep` arms need a binding, as in `catch deep e: Type`",
+                ));
+            }
+            let _arrow: Token![=>] = input.parse()?;
+            let body = input.parse()?;
+            if input.peek(Token![,]) {
+                let _comma: Token![,] = input.parse()?;
+            }
+            let mut err_types = err_types;
+            catch_strings(&mut err_types);
+            return Ok(Catch {
+                attrs,
+                catch_kw,
+                error: None,
+                by_ref: None,
+                deep: false,
+                panic: false,
+                err_types,
+                guard: None,
+                retry: None,
+                rethrows: false,
+                resume: None,
+                body,
+            });
+        }
+        let (error, err_types) = if let Some(panic) = panic {
+            let error = if input.peek(Token![_]) {
+                Pat::Wild(PatWild {
+                    attrs: vec![],
+                    underscore_token: input.parse()?,
+                })
+            } else {
+                ident_pat(input.parse()?)
+            };
+            let ty = parse_quote_spanned!(panic.span=> ::try_catch::Panicked);
+            (error, vec![ErrType { ty, kinds: vec![] }])
+        } else if let Some(none) = none {
+            let error = Pat::Wild(PatWild {
+                attrs: vec![],
+                underscore_token: Token![_](none.span),
+            });
+            let ty = parse_quote_spanned!(none.span=> ::try_catch::NoneError);
+            (error, vec![ErrType { ty, kinds: vec![] }])
+        } else if let Some(pattern) = pattern {
+            pattern
+        } else if input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let (error, err_types) = parse_parenthesized(&content)?;
+            (ident_pat(error), err_types)
+        } else {
+            let error = if input.peek(Token![_]) {
+                Pat::Wild(PatWild {
+                    attrs: vec![],
+                    underscore_token: input.parse()?,
+                })
+            } else {
+                ident_pat(input.parse()?)
+            };
+            let err_types = if input.peek(Token![:]) {
+                let _colon: Token![:] = input.parse()?;
+                parse_err_types(input)?
+            } else {
+                vec![]
+            };
+            (error, err_types)
+        };
+        if let Some(deep) = deep {
+            if by_ref.is_some() {
+                return Err(Error::new(
+                    deep.span,
+                    "`deep` arms already borrow the error, they cannot catch by reference",
+                ));
+            }
+            if !matches!(&err_types[..], [ErrType { ty, .. }] if !matches!(ty, Type::TraitObject(_)))
+            {
+                return Err(Error::new(
+                    deep.span,
+                    "`deep` arms need a single concrete error type, as in `catch deep e: Type`",
+                ));
+            }
+        }
+        let guard = if input.peek(Token![if]) {
+            let _if_kw: Token![if] = input.parse()?;
+            Some(Expr::parse_without_eager_brace(input)?)
+        } else {
+            None
+        };
+        let retry = parse_retry(&input)?;
+        if let (Some(_), Some(_)) = (by_ref, retry) {
+            return Err(input.error("arms catching by reference cannot `retry`"));
+        }
+        let mut rethrows = false;
+        let mut resume = None;
+        let original = match (&error, &err_types[..]) {
+            // `dyn Trait` and `deep` arms only borrow the error, which is
+            // still in its box.
+            (
+                _,
+                [ErrType {
+                    ty: Type::TraitObject(_),
+                    ..
+                }],
+            ) => Some(Ident::new("__try_catch_error", Span::mixed_site()).into_token_stream()),
+            _ if deep.is_some() => {
+                Some(Ident::new("__try_catch_error", Span::mixed_site()).into_token_stream())
+            }
+            (Pat::Ident(PatIdent { ident, .. }), _) => Some(ident.to_token_stream()),
+            _ => None,
+        };
+        let mut rewrite_arm = |block: TokenStream2| -> Result<Expr> {
+            let block = rewrite_rethrow(block, original.as_ref(), &mut rethrows)?;
+            let block = rewrite_resume(block, &mut resume)?;
+            syn::parse2(block)
+        };
+        let body = if input.peek(Token![=>]) {
+            let _arrow: Token![=>] = input.parse()?;
+            let body = if by_ref.is_none() && starts_arm_statement(input) {
+                // `=> rethrow error,` runs up to the comma, as a block would.
+                let mut body = TokenStream2::new();
+                while !input.peek(Token![,]) && !ends_arm(input) {
+                    body.extend([input.parse::<TokenTree>()?]);
+                }
+                rewrite_arm(quote![{ #body }])?
+            } else {
+                input.parse()?
+            };
+            if input.peek(Token![,]) {
+                let _comma: Token![,] = input.parse()?;
+            }
+            body
+        } else if by_ref.is_none() && input.peek(token::Brace) {
+            let block: proc_macro2::Group = input.parse()?;
+            rewrite_arm(block.to_token_stream())?
+        } else if input.peek(token::Brace) {
+            Expr::Block(parse_block(&input)?)
+        } else {
+            return Err(missing_body(&catch_kw));
+        };
+        let mut error = error;
+        let mut err_types = err_types;
+        // Binding the string by value takes it out of its `DisplayError`.
+        if catch_strings(&mut err_types)
+            && err_types.len() == 1
+            && by_ref.is_none()
+            && deep.is_none()
+        {
+            if let Pat::Ident(PatIdent { subpat: None, .. }) = &error {
+                error = parse_quote_spanned![error.span()=> ::try_catch::DisplayError(#error)];
+            }
+        }
+        Ok(Catch {
+            attrs,
+            catch_kw,
+            error: Some(error),
+            by_ref,
+            deep: deep.is_some(),
+            panic: panic.is_some(),
+            err_types,
+            guard,
+            retry,
+            rethrows,
+            resume,
+            body,
+        })
+    }
+}
+
+impl Parse for VariantCatch {
+    fn parse(input: parse::ParseStream) -> Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let catch_kw = parse_catch_kw(input)?;
+        // `MyError::NotFound { .. }` would parse as a struct pattern, so the
+        // pattern ends at `=>` or at the last brace group before the next arm.
+        let mut head = TokenStream2::new();
+        while !input.is_empty() && !input.peek(Token![=>]) {
+            if input.peek(token::Brace) {
+                let fork = input.fork();
+                let _body: proc_macro2::TokenTree = fork.parse()?;
+                if ends_arm(&fork) {
+                    break;
+                }
+            }
+            head.extend([input.parse::<proc_macro2::TokenTree>()?]);
+        }
+        let (pat, guard) = (|head: parse::ParseStream| {
+            let mut pat: Pat = head.parse()?;
+            if head.peek(Token![|]) {
+                let mut cases = Punctuated::new();
+                cases.push_value(pat);
+                while head.peek(Token![|]) {
+                    cases.push_punct(head.parse()?);
+                    cases.push_value(head.parse()?);
+                }
+                pat = Pat::Or(PatOr {
+                    attrs: vec![],
+                    leading_vert: None,
+                    cases,
+                });
+            }
+            let guard = if head.peek(Token![if]) {
+                let _if_kw: Token![if] = head.parse()?;
+                Some(head.parse()?)
+            } else {
+                None
+            };
+            Ok((pat, guard))
+        })
+        .parse2(head)?;
+        let body = if input.peek(Token![=>]) {
+            let _arrow: Token![=>] = input.parse()?;
+            let body = input.parse()?;
+            if input.peek(Token![,]) {
+                let _comma: Token![,] = input.parse()?;
+            }
+            body
+        } else if input.peek(token::Brace) {
+            Expr::Block(parse_block(&input)?)
+        } else {
+            return Err(missing_body(&catch_kw));
+        };
+        Ok(VariantCatch {
+            attrs,
+            pat,
+            guard,
+            body,
+        })
+    }
+}
+
+/// Parses the `catch` starting an arm, explaining the keywords of other
+/// languages that people tend to write instead.
+fn parse_catch_kw(input: parse::ParseStream) -> Result<Ident> {
+    if input.peek(Token![else]) {
+        return Err(
+            input.error("`else` must come right after the try block, before the catch arms")
+        );
+    }
+    if input.peek(Token![try]) {
+        return Err(input.error(
+            "`catch!` takes a single try block, expected a catch arm, as in `catch e: Type { .. }`",
+        ));
+    }
+    if !input.peek(Ident) {
+        let rest: TokenStream2 = input.fork().parse()?;
+        return Err(Error::new_spanned(
+            rest,
+            "unexpected tokens after the catch arms, \
+             expected another `catch e: Type { .. }` arm or `finally { .. }`",
+        ));
+    }
+    let catch_kw: Ident = input.parse()?;
+    match &*catch_kw.to_string() {
+        "catch" => Ok(catch_kw),
+        "except" | "rescue" => Err(Error::new(
+            catch_kw.span(),
+            format!(
+                "errors are caught with `catch`, not `{}`, as in `catch e: io::Error {{ .. }}`",
+                catch_kw,
+            ),
+        )),
+        _ => Err(Error::new(
+            catch_kw.span(),
+            "expected a catch arm, as in `catch e: Type { .. }`",
+        )),
+    }
+}
+
+fn missing_body(catch_kw: &Ident) -> Error {
+    Error::new(
+        catch_kw.span(),
+        "this catch arm has no body, expected a block or `=> value` after it, \
+         as in `catch e: io::Error { .. }` or `catch e: io::Error => value,`",
+    )
+}
+
+/// The label of the block around arm bodies that `rethrow` breaks out of.
+pub fn arm_label() -> Lifetime {
+    Lifetime::new("'__try_catch_arm", Span::mixed_site())
+}
+
+/// The label of the block around arm bodies that `resume` breaks out of.
+pub fn resume_label() -> Lifetime {
+    Lifetime::new("'__try_catch_resume", Span::mixed_site())
+}
+
+/// Replaces `resume value;` statements in an arm body, and `resume value` as
+/// the value of a match arm in it, with a `break` out of the arm with
+/// `Some(value)`, recording the first one in `resume`.
+fn rewrite_resume(tokens: TokenStream2, resume: &mut Option<Span>) -> Result<TokenStream2> {
+    let label = resume_label();
+    rewrite_statements(
+        tokens,
+        Delimiter::None,
+        "resume",
+        &mut |resume_kw, value| {
+            if value.is_empty() {
+                return Err(Error::new(
+                    resume_kw.span(),
+                    "expected a value after `resume`",
+                ));
+            }
+            resume.get_or_insert(resume_kw.span());
+            Ok(quote![break #label ::core::option::Option::Some(#value)])
+        },
+    )
+}
+
+/// Replaces `rethrow;`, `rethrow error;` and `rethrow with message;` statements
+/// in an arm body, and the same as the value of a match arm in it, with a
+/// `break` out of the arm with the error, boxed. `original`
+/// is the caught error, `None` when the arm does not bind it.
+fn rewrite_rethrow(
+    tokens: TokenStream2,
+    original: Option<&TokenStream2>,
+    rethrows: &mut bool,
+) -> Result<TokenStream2> {
+    let label = arm_label();
+    rewrite_statements(
+        tokens,
+        Delimiter::None,
+        "rethrow",
+        &mut |rethrow, mut value| {
+            let mut tokens = value.clone().into_iter();
+            let with = matches!(tokens.next(), Some(TokenTree::Ident(with)) if with == "with");
+            if with {
+                value = tokens.collect();
+                if value.is_empty() {
+                    return Err(Error::new(
+                        rethrow.span(),
+                        "expected a message after `rethrow with`",
+                    ));
+                }
+            }
+            if value.is_empty() || with {
+                let original = match original {
+                    Some(original) => original.clone(),
+                    None => {
+                        return Err(Error::new(
+                            rethrow.span(),
+                            "`rethrow` needs the error bound to a name, \
+                         or an error to propagate as in `rethrow error`",
+                        ))
+                    }
+                };
+                value = if !with {
+                    original
+                } else if let Ok(message) = syn::parse2::<LitStr>(value.clone()) {
+                    // Like `format!`, string literals can capture variables.
+                    quote![::try_catch::Rethrown::new(::try_catch::__private::format!(#message), #original)]
+                } else {
+                    quote![::try_catch::Rethrown::new(#value, #original)]
+                };
+            }
+            *rethrows = true;
+            Ok(quote![
+                break #label ::core::result::Result::Err(
+                    ::core::convert::From::from(#value)
+                )
+            ])
+        },
+    )
+}
+
+/// Replaces `throw error;` statements in a try block with an early return of
+/// the error from the closure the block is expanded into.
+fn rewrite_throw(block: TokenStream2) -> Result<TokenStream2> {
+    rewrite_statements(block, Delimiter::None, "throw", &mut |throw, value| {
+        if value.is_empty() {
+            return Err(Error::new(throw.span(), "expected an error after `throw`"));
+        }
+        // Raised through `?`, which leaves the try block whether it runs in a
+        // closure or in place. Spanned at `throw` so that the statement maps
+        // back to the source.
+        Ok(quote::quote_spanned![throw.span()=>
+            match ::core::result::Result::<::core::convert::Infallible, _>::Err(#value)? {}
+        ])
+    })
+}
+
+/// Calls `rewrite` on each statement of `tokens` starting with `keyword`, with
+/// the tokens up to the next `;`, or up to the `,` that ends the value of a
+/// match arm, and replaces the statement with its output.
+/// Invocations of other macros, such as a nested `catch!`, are left alone, as
+/// are the uses of `keyword` as a name, such as `error.throw()` or `let throw`.
+fn rewrite_statements(
+    tokens: TokenStream2,
+    delimiter: Delimiter,
+    keyword: &str,
+    rewrite: &mut dyn FnMut(&Ident, TokenStream2) -> Result<TokenStream2>,
+) -> Result<TokenStream2> {
+    let mut out: Vec<TokenTree> = vec![];
+    let mut tokens = tokens.into_iter();
+    let mut after_bang = false;
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident)
+                if ident == keyword
+                    && starts_statement(delimiter, &out, tokens.clone().next().as_ref()) =>
+            {
+                let arm = follows_arrow(&out);
+                let mut value = TokenStream2::new();
+                let mut end = None;
+                for token in tokens.by_ref() {
+                    match token {
+                        TokenTree::Punct(punct)
+                            if punct.as_char() == ';' || arm && punct.as_char() == ',' =>
+                        {
+                            end = Some(punct);
+                            break;
+                        }
+                        token => value.extend([token]),
+                    }
+                }
+                out.extend(rewrite(&ident, value)?);
+                out.extend(end.map(TokenTree::Punct));
+                after_bang = false;
+            }
+            TokenTree::Group(group) if !after_bang => {
+                let stream =
+                    rewrite_statements(group.stream(), group.delimiter(), keyword, rewrite)?;
+                let mut rewritten = proc_macro2::Group::new(group.delimiter(), stream);
+                rewritten.set_span(group.span());
+                out.push(TokenTree::Group(rewritten));
+            }
+            token => {
+                after_bang = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '!');
+                out.push(token);
+            }
+        }
+    }
+    Ok(out.into_iter().collect())
+}
+
+/// Whether a keyword preceded by `before` in a group delimited by `delimiter`
+/// and followed by `next` starts a statement or the value of a `let`, an
+/// assignment or a match arm, rather than being used as a name: as a path
+/// segment, a field or a method, in a pattern such as `let throw`, or as an
+/// argument, a call or a macro.
+fn starts_statement(delimiter: Delimiter, before: &[TokenTree], next: Option<&TokenTree>) -> bool {
+    let is_punct = |token: &TokenTree, ch: char| matches!(token, TokenTree::Punct(punct) if punct.as_char() == ch);
+    let starts = match before {
+        [] => matches!(delimiter, Delimiter::Brace | Delimiter::None),
+        [.., TokenTree::Group(group)] => group.delimiter() == Delimiter::Brace,
+        [.., last] if is_punct(last, ';') => true,
+        _ if follows_arrow(before) => true,
+        // A lone `=`, rather than the end of `==` or `+=`.
+        [.., TokenTree::Punct(first), last] if is_punct(last, '=') => {
+            first.spacing() == Spacing::Alone
+        }
+        [.., last] => is_punct(last, '='),
+    };
+    let named = match next {
+        Some(TokenTree::Group(group)) => group.delimiter() == Delimiter::Parenthesis,
+        Some(TokenTree::Punct(punct)) => match punct.as_char() {
+            // `throw ::std::io::Error::new(..)` starts the value with a path.
+            ':' => punct.spacing() == Spacing::Alone,
+            // `=> rethrow,` is a whole match arm.
+            ',' => !follows_arrow(before),
+            '!' | '.' | '=' | '|' | '?' => true,
+            _ => false,
+        },
+        _ => false,
+    };
+    starts && !named
+}
+
+/// Whether `before` ends with the `=>` of a match arm.
+fn follows_arrow(before: &[TokenTree]) -> bool {
+    match before {
+        [.., TokenTree::Punct(eq), TokenTree::Punct(gt)] => {
+            eq.as_char() == '=' && eq.spacing() == Spacing::Joint && gt.as_char() == '>'
+        }
+        _ => false,
+    }
+}
+
+/// Whether the body of an arrow arm starts with `rethrow` or `resume`.
+fn starts_arm_statement(input: parse::ParseStream) -> bool {
+    let fork = input.fork();
+    match fork.parse::<TokenTree>() {
+        Ok(TokenTree::Ident(ident)) if ident == "rethrow" || ident == "resume" => {
+            // A bare `=> rethrow,` ends at the comma.
+            fork.peek(Token![,]) || ends_arm(&fork) || {
+                let next = fork.parse::<TokenTree>().ok();
+                starts_statement(Delimiter::Brace, &[], next.as_ref())
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Whether `input` is past the end of a catch arm.
+fn ends_arm(input: parse::ParseStream) -> bool {
+    input.is_empty()
+        || input.peek(Token![#])
+        || input.peek(kw::finally)
+        || input
+            .fork()
+            .parse::<Ident>()
+            .is_ok_and(|ident| ident == "catch")
+}
+
+impl Parse for Resource {
+    fn parse(input: parse::ParseStream) -> Result<Self> {
+        let pat = input.parse()?;
+        let ty = if input.peek(Token![:]) {
+            let _colon: Token![:] = input.parse()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        let _eq: Token![=] = input.parse()?;
+        let init = Expr::parse_without_eager_brace(input)?;
+        Ok(Resource { pat, ty, init })
+    }
+}
+
+impl ToTokens for Resource {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let Resource { pat, ty, init } = self;
+        let ty = ty.iter();
+        tokens.extend(quote![let #pat #(: #ty)* = #init;]);
+    }
+}
+
+/// Parses arms that destructure the error, such as `catch MyError { code, .. }: MyError`
+/// or `catch ParseIntError { .. }`, where the type is taken from the struct pattern.
+/// Plain bindings such as `catch e: Type` are left to the caller.
+fn parse_pattern(input: parse::ParseStream) -> Result<Option<(Pat, Vec<ErrType>)>> {
+    let fork = input.fork();
+    let pat = match fork.parse::<Pat>() {
+        Ok(Pat::Ident(PatIdent { subpat: None, .. }) | Pat::Wild(_)) | Err(_) => return Ok(None),
+        Ok(pat) => pat,
+    };
+    if fork.peek(Token![:]) && !fork.peek(Token![::]) {
+        input.advance_to(&fork);
+        let _colon: Token![:] = input.parse()?;
+        let err_types = parse_err_types(input)?;
+        if let [_, second, ..] = &err_types[..] {
+            return Err(Error::new(
+                second.ty.span(),
+                "a pattern can only destructure a single error type",
+            ));
+        }
+        return Ok(Some((pat, err_types)));
+    }
+    if let Pat::Struct(PatStruct { path, .. }) = &pat {
+        if fork.peek(token::Brace)
+            || fork.peek(Token![=>])
+            || fork.peek(Token![if])
+            || fork.peek(kw::retry)
+        {
+            input.advance_to(&fork);
+            let ty = Type::Path(TypePath {
+                qself: None,
+                path: path.clone(),
+            });
+            return Ok(Some((pat, vec![ErrType { ty, kinds: vec![] }])));
+        }
+    }
+    Ok(None)
+}
+
+fn ident_pat(ident: Ident) -> Pat {
+    Pat::Ident(PatIdent {
+        attrs: vec![],
+        by_ref: None,
+        mutability: None,
+        ident,
+        subpat: None,
+    })
+}
+
+/// Parses the C#-style `catch (e: Type)`, `catch (Type e)` and `catch (e)`.
+fn parse_parenthesized(input: parse::ParseStream) -> Result<(Ident, Vec<ErrType>)> {
+    let binding_first =
+        input.peek(Ident) && !input.peek2(Token![::]) && !input.peek2(Token![<]) && {
+            let fork = input.fork();
+            let _error: Ident = fork.parse()?;
+            fork.is_empty() || fork.peek(Token![:])
+        };
+    let out = if binding_first {
+        let error = input.parse()?;
+        let err_types = if input.is_empty() {
+            vec![]
+        } else {
+            let _colon: Token![:] = input.parse()?;
+            parse_err_types(input)?
+        };
+        (error, err_types)
+    } else {
+        let err_types = parse_err_types(input)?;
+        (input.parse()?, err_types)
+    };
+    if !input.is_empty() {
+        return Err(input.error("expected `)` after the catch binding"));
+    }
+    Ok(out)
+}
+
+/// Whether `pattern` binds the whole error, as in `catch e: Type`, rather than
+/// destructuring it.
+pub fn is_binding(pattern: &Pat) -> bool {
+    matches!(
+        pattern,
+        Pat::Ident(PatIdent { subpat: None, .. }) | Pat::Wild(_)
+    )
+}
+
+/// Replaces the `String` and `&'static str` types of an arm with the
+/// `DisplayError` that strings raised in the try block are boxed as, returning
+/// whether there were any.
+fn catch_strings(err_types: &mut [ErrType]) -> bool {
+    let mut found = false;
+    for ErrType { ty, .. } in err_types {
+        let string = match &*ty {
+            Type::Path(TypePath { qself: None, path }) => path
+                .segments
+                .last()
+                .is_some_and(|last| last.ident == "String" && last.arguments.is_empty()),
+            _ => false,
+        };
+        let str = match &*ty {
+            Type::Reference(TypeReference {
+                lifetime,
+                mutability: None,
+                elem,
+                ..
+            }) => {
+                lifetime
+                    .as_ref()
+                    .map_or(true, |lifetime| lifetime.ident == "static")
+                    && matches!(&**elem, Type::Path(TypePath { qself: None, path }) if path.is_ident("str"))
+            }
+            _ => false,
+        };
+        if string {
+            *ty = parse_quote_spanned![ty.span()=> ::try_catch::DisplayError<#ty>];
+        } else if str {
+            *ty = parse_quote_spanned![ty.span()=> ::try_catch::DisplayError<&'static str>];
+        }
+        found |= string || str;
+    }
+    found
+}
+
+/// Whether the error carrier `ty` is a `Box`, which downcasts to a `Box` of
+/// the error rather than to the error itself.
+pub fn is_box(ty: &Type) -> bool {
+    match ty {
+        Type::Path(TypePath { path, .. }) => path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Box"),
+        _ => false,
+    }
+}
+
+/// `ty` as written, without the spaces that token streams put around `::`
+/// and angle brackets.
+fn type_name(ty: &Type) -> String {
+    ty.to_token_stream()
+        .to_string()
+        .replace(" :: ", "::")
+        .replace(":: ", "::")
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+}
+
+/// Whether `a` and `b` are paths where one ends with the other, as in
+/// `io::Error` and `std::io::Error`.
+fn same_path_suffix(a: &Type, b: &Type) -> bool {
+    let segments = |ty: &Type| match ty {
+        Type::Path(TypePath { qself: None, path }) => Some(
+            path.segments
+                .iter()
+                .map(|segment| segment.to_token_stream().to_string())
+                .collect::<Vec<_>>(),
+        ),
+        _ => None,
+    };
+    match (segments(a), segments(b)) {
+        (Some(a), Some(b)) => a.ends_with(&b) || b.ends_with(&a),
+        _ => false,
+    }
+}
+
+/// Parses the `|`-separated types caught by an arm, as in `catch e: io::Error | fmt::Error`.
+fn parse_err_types(input: parse::ParseStream) -> Result<Vec<ErrType>> {
+    let mut err_types: Vec<ErrType> = vec![parse_err_type(input)?];
+    while input.peek(Token![|]) {
+        let _or: Token![|] = input.parse()?;
+        let err_type = parse_err_type(input)?;
+        let key = err_type.to_token_stream().to_string();
+        if err_types
+            .iter()
+            .any(|other| other.to_token_stream().to_string() == key)
+        {
+            return Err(Error::new(
+                err_type.ty.span(),
+                "this type is already caught by this arm",
+            ));
+        }
+        err_types.push(err_type);
+    }
+    Ok(err_types)
+}
+
+impl ToTokens for ErrType {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let ErrType { ty, kinds } = self;
+        ty.to_tokens(tokens);
+        if !kinds.is_empty() {
+            tokens.extend(quote![(#(#kinds)|*)]);
+        }
+    }
+}
+
+/// Parses the type of a catch arm, followed by an optional list of kinds in
+/// parentheses as in `io::Error(NotFound | PermissionDenied)`. Kinds written as
+/// a single identifier are looked up in `std::io::ErrorKind`.
+fn parse_err_type(input: parse::ParseStream) -> Result<ErrType> {
+    // `Type` would read the parentheses as `Fn(..)` sugar, so the path in
+    // front of them is collected by hand.
+    let fork = input.fork();
+    let mut path = TokenStream2::new();
+    let mut depth = 0;
+    loop {
+        if fork.peek(Token![<]) {
+            depth += 1;
+        } else if fork.peek(Token![>]) {
+            depth -= 1;
+        } else if depth == 0 && fork.peek(token::Paren) && !path.is_empty() {
+            break;
+        } else if depth == 0 && !fork.peek(Ident) && !fork.peek(Token![::]) {
+            return Ok(ErrType {
+                ty: input.parse()?,
+                kinds: vec![],
+            });
+        }
+        if fork.is_empty() {
+            return Ok(ErrType {
+                ty: input.parse()?,
+                kinds: vec![],
+            });
+        }
+        if fork.peek(Token![::]) {
+            path.extend(fork.parse::<Token![::]>()?.into_token_stream());
+        } else {
+            path.extend([fork.parse::<proc_macro2::TokenTree>()?]);
+        }
+    }
+    input.advance_to(&fork);
+    let ty = syn::parse2(path)?;
+    let content;
+    parenthesized!(content in input);
+    if content.peek(Ident) && content.peek2(Token![=]) {
+        let kind_kw: Ident = content.parse()?;
+        if kind_kw != "kind" {
+            return Err(Error::new(kind_kw.span(), "expected `kind`"));
+        }
+        let _eq: Token![=] = content.parse()?;
+    }
+    let mut kinds = vec![];
+    loop {
+        let kind: Path = content.parse()?;
+        kinds.push(match kind.get_ident() {
+            Some(kind) => parse_quote!(::std::io::ErrorKind::#kind),
+            None => kind,
+        });
+        if content.is_empty() {
+            break;
+        }
+        let _or: Token![|] = content.parse()?;
+    }
+    Ok(ErrType { ty, kinds })
+}
+
+/// Tells `catch e: Type`, `catch e { .. }` and `catch e => ..` apart from handler arms
+/// such as `catch io::Error => handler`. A lone identifier followed by `=>` is taken
+/// as a type when it starts with an uppercase letter.
+fn starts_with_binding(input: parse::ParseStream) -> bool {
+    if input.peek(Token![_]) {
+        return true;
+    }
+    if !input.peek(Ident)
+        || input.peek2(Token![::])
+        || input.peek2(Token![<])
+        || input.peek2(token::Paren)
+    {
+        return false;
+    }
+    if input.peek2(Token![=>]) {
+        let fork = input.fork();
+        let ident: Ident = fork.parse().unwrap();
+        return !ident.to_string().starts_with(char::is_uppercase);
+    }
+    true
+}
+
+impl Parse for Fallback {
+    fn parse(input: parse::ParseStream) -> Result<Self> {
+        let _or_kw: kw::or = input.parse()?;
+        let error = if input.peek(Ident) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        let block = parse_try_block(&input)?;
+        Ok(Fallback { error, block })
+    }
+}
+
+impl Parse for Else {
+    fn parse(input: parse::ParseStream) -> Result<Self> {
+        let _else_kw: Token![else] = input.parse()?;
+        let value = input.parse()?;
+        let block = parse_block(&input)?;
+        Ok(Else { value, block })
+    }
+}
+
+/// Whether `input`, the statements of the try block, awaits outside of the
+/// async blocks and closures it contains, whose awaits belong to them.
+pub fn is_async(input: TokenStream2) -> bool {
+    match syn::parse2::<Block>(quote![{ #input }]) {
+        Ok(mut block) => {
+            let mut finder = AwaitFinder { found: false };
+            finder.visit_block_mut(&mut block);
+            finder.found
+        }
+        Err(_) => awaits(input),
+    }
+}
+
+struct AwaitFinder {
+    found: bool,
+}
+
+impl VisitMut for AwaitFinder {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Await(_) => self.found = true,
+            Expr::Async(_) | Expr::Closure(_) => {}
+            _ => visit_mut::visit_expr_mut(self, expr),
+        }
+    }
+
+    fn visit_item_mut(&mut self, item: &mut Item) {
+        // Macros in statement position, other items are not part of the block.
+        if let Item::Macro(ItemMacro {
+            ident: None, mac, ..
+        }) = item
+        {
+            self.visit_macro_mut(mac);
+        }
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut Macro) {
+        self.found |= awaits(mac.tokens.clone());
+    }
+}
+
+/// Whether the tokens of a macro call have a `.` followed by `await` outside
+/// of the async blocks they contain.
+fn awaits(input: TokenStream2) -> bool {
+    let mut dot = false;
+    let mut in_async = false;
+    for token in input {
+        match &token {
+            TokenTree::Ident(ident) if dot && ident == "await" => return true,
+            TokenTree::Group(group) if !in_async && awaits(group.stream()) => return true,
+            _ => {}
+        }
+        // An async block is `async` and an optional `move` before its braces.
+        in_async = match &token {
+            TokenTree::Ident(ident) if ident == "async" => true,
+            TokenTree::Ident(ident) if ident == "move" => in_async,
+            _ => false,
+        };
+        dot = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '.');
+    }
+    false
+}
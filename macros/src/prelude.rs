@@ -0,0 +1,4 @@
+pub use proc_macro::TokenStream;
+pub use proc_macro2::TokenStream as TokenStream2;
+pub use quote::quote;
+pub use syn::{Ident, *};
@@ -0,0 +1,53 @@
+//! `catch_stream!`, which runs the catch arms over each item of a stream of
+//! results.
+
+use crate::parse::{Source, TryCatch};
+use crate::prelude::*;
+use crate::template::template;
+
+use proc_macro2::Span;
+
+pub fn catch_stream(try_catch: TryCatch) -> Result<TokenStream2> {
+    try_catch.check_resume()?;
+    let mut try_catch = try_catch.into_result("catch_stream!")?;
+    if try_catch.lazy {
+        return Err(Error::new(
+            Span::call_site(),
+            "`lazy` cannot be used with `catch_stream!`, whose arms run as items arrive",
+        ));
+    }
+    let item = Ident::new("__try_catch_item", Span::mixed_site());
+    let stream = match &mut try_catch.source {
+        Source::Result(stream) => std::mem::replace(&mut **stream, parse_quote![#item]),
+        Source::Try(_) => {
+            return Err(Error::new(
+                Span::call_site(),
+                "`catch_stream!` takes a stream rather than a try block, \
+                 as in `catch_stream! { match stream { catch e: Type => .. } }`",
+            ))
+        }
+    };
+    // `continue` in an arm comes back to an item that was already taken, and
+    // skips it.
+    let pending = Ident::new("__try_catch_pending", Span::mixed_site());
+    let handled = Ident::new("__try_catch_handled", Span::mixed_site());
+    let handle = template(try_catch);
+    Ok(quote! {
+        ::try_catch::CatchStream::new(#stream, move |#item| {
+            let mut #pending = ::core::option::Option::Some(#item);
+            let mut #handled = ::core::option::Option::None;
+            loop {
+                let #item = match ::core::option::Option::take(&mut #pending) {
+                    ::core::option::Option::Some(#item) => #item,
+                    ::core::option::Option::None => break,
+                };
+                // Arms that `continue` leave the `Ok` around them unreachable.
+                #[allow(unreachable_code, clippy::diverging_sub_expression)]
+                let #item = #handle;
+                #handled = ::core::option::Option::Some(#item);
+                break;
+            }
+            #handled
+        })
+    })
+}
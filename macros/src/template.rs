@@ -0,0 +1,1092 @@
+use crate::inline::{find_exit, inline};
+use crate::parse::*;
+use crate::prelude::*;
+use proc_macro2::{Literal, Span, TokenTree};
+
+use quote::{quote_spanned, ToTokens};
+use syn::{
+    parse_quote_spanned,
+    spanned::Spanned,
+    visit_mut::{self, VisitMut},
+};
+
+pub fn template(mut try_catch: TryCatch) -> TokenStream2 {
+    // In `propagate` mode, boxed errors are carried along with the conversion
+    // into the error type of the function, so that they are returned as they
+    // were raised.
+    let propagates = matches!(try_catch.unmatched, Unmatched::Propagate(_))
+        && !is_handled(&try_catch.catches, &try_catch.into, &try_catch.unmatched);
+    // Traced errors are boxed with their backtrace instead.
+    let propagating = propagates
+        && try_catch.carrier.is_none()
+        && try_catch.error_enum.is_none()
+        && try_catch.traced.is_none();
+    if propagating {
+        try_catch.carrier = Some(parse_quote![::try_catch::__private::Propagating<_>]);
+    }
+    own_braces(&mut try_catch);
+    convert_errors(&mut try_catch, propagating);
+    let warnings: Vec<_> = try_catch
+        .warnings
+        .drain(..)
+        .map(|(span, message)| warning(span, &message))
+        .collect();
+    // Custom carriers decide for themselves what they can be downcast to.
+    let assertions: Vec<_> = match &try_catch.carrier {
+        Some(carrier) if !is_box(carrier) && !propagating => vec![],
+        _ => try_catch
+            .catches
+            .iter()
+            .filter(|catch| !catch.panic)
+            .flat_map(|catch| &catch.err_types)
+            // `dyn Trait` arms are checked to implement `ErrorCast` instead.
+            .filter(|ErrType { ty, .. }| !matches!(ty, Type::TraitObject(_)))
+            .map(|ErrType { ty, .. }| {
+                quote_spanned![ty.span()=> ::try_catch::__private::assert_catchable::<#ty>();]
+            })
+            .collect(),
+    };
+    let is_async = try_catch.is_async;
+    // Whether the try block can leave the expression with `return`, `break`
+    // or `continue`, past `cleanup` and `finally`, which then run from drop
+    // guards.
+    let try_exits = match &try_catch.source {
+        Source::Try(try_block) => {
+            find_exit(try_block.block.to_token_stream(), false).is_some()
+                || try_block
+                    .fallbacks
+                    .iter()
+                    .any(|fallback| find_exit(fallback.block.to_token_stream(), false).is_some())
+        }
+        Source::Result(_) => false,
+    };
+    // Whether an arm can leave the expression, with `?` as well, past `finally`.
+    let arms_exit = try_catch
+        .catches
+        .iter()
+        .map(|catch| catch.body.to_token_stream())
+        .chain(
+            try_catch
+                .else_clause
+                .iter()
+                .map(|else_clause| else_clause.block.to_token_stream()),
+        )
+        .chain(
+            try_catch
+                .error_enum
+                .iter()
+                .flat_map(|error_enum| &error_enum.catches)
+                .map(|catch| catch.body.to_token_stream()),
+        )
+        .any(|body| find_exit(body, true).is_some());
+    let custom_carrier = try_catch
+        .carrier
+        .as_ref()
+        .is_some_and(|carrier| !is_box(carrier));
+    let result = Ident::new("__try_catch_block", Span::mixed_site());
+    let result_err = Ident::new("__try_catch_error", Span::mixed_site());
+    let result_ok = Ident::new("__try_catch_ok", Span::mixed_site());
+    // Annotating the success type gives inference an anchor, and makes the
+    // try block the place where a mismatch is reported.
+    let (ok_type, ok) = match &try_catch.source {
+        Source::Try(try_block) => match &try_block.ty {
+            Some(ty) => (
+                ty.to_token_stream(),
+                quote_spanned![ty.span()=> ::core::result::Result::Ok::<#ty, _>],
+            ),
+            None => (quote![_], quote![::core::result::Result::Ok]),
+        },
+        Source::Result(_) => (quote![_], quote![::core::result::Result::Ok]),
+    };
+    // The value of the try block, where mismatches with the arms are reported.
+    let value_span = match &try_catch.source {
+        Source::Try(try_block) => match try_block.block.block.stmts.last() {
+            Some(Stmt::Expr(tail)) => tail.span(),
+            _ => try_block.block.span(),
+        },
+        Source::Result(result) => result.span(),
+    };
+    let carrier = match &try_catch.carrier {
+        Some(carrier) => carrier.to_token_stream(),
+        None => quote![::try_catch::__private::Box<dyn ::core::error::Error>],
+    };
+    let result_type = match &try_catch.error_enum {
+        Some(ErrorEnum { ty, .. }) => quote![::core::result::Result<#ok_type, #ty>],
+        None => quote![::core::result::Result<#ok_type, #carrier>],
+    };
+
+    let capture = if try_catch.capture_move {
+        quote![move]
+    } else {
+        quote![]
+    };
+    let flatten = matches!(&try_catch.source, Source::Try(try_block) if try_block.flatten);
+    // The block may diverge, as with `process::exit`, leaving the `Ok` around
+    // it unreachable.
+    let ok_block = |block: TokenStream2| {
+        let value = Ident::new("__try_catch_value", Span::mixed_site());
+        let block = if flatten { quote![#block?] } else { block };
+        quote![{
+            #[allow(unreachable_code, clippy::diverging_sub_expression)]
+            let #value = #ok(#block);
+            #value
+        }]
+    };
+    let future_block = |block: TokenStream2| {
+        let block = ok_block(block);
+        quote![async #capture {#block}]
+    };
+    // With a `catch panic` arm, a panic in the block becomes a `Panicked` error.
+    let catches_panics = try_catch.catches.iter().any(|catch| catch.panic);
+    // A `move` block takes ownership of what it captures, and blocks that are
+    // retried run in a loop, which unlabeled `break`s would target.
+    let retries = try_catch.catches.iter().any(|catch| catch.retry.is_some())
+        || matches!(&try_catch.source, Source::Try(try_block) if try_block.retry.is_some());
+    let capture_move = try_catch.capture_move;
+    let invoke_block = |block: TokenStream2| {
+        // Where the block runs, if not in place.
+        let (invoke, place) = if is_async {
+            let future = future_block(block.clone());
+            (quote![#future.await], Some("an async block".to_string()))
+        } else if catches_panics {
+            let ok_block = ok_block(block.clone());
+            let invoke = quote![
+                match ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(#capture || #ok_block)) {
+                    ::core::result::Result::Ok(#result) => #result,
+                    ::core::result::Result::Err(#result_err) => ::core::result::Result::Err(
+                        ::core::convert::From::from(::try_catch::Panicked::new(#result_err)),
+                    ),
+                }
+            ];
+            let place = "a closure, to catch its panics";
+            (invoke, Some(place.to_string()))
+        } else {
+            let ok_block = ok_block(block.clone());
+            let inlined = if capture_move {
+                Err("it is a `move` try block".to_string())
+            } else if retries {
+                Err("it is retried".to_string())
+            } else {
+                inline(ok_block.clone())
+            };
+            match inlined {
+                Ok(inline) => (inline, None),
+                Err(reason) => (
+                    quote![(#capture || #ok_block)()],
+                    Some(format!("a closure, since {}", reason)),
+                ),
+            }
+        };
+        match place.and_then(|place| leaves_block(block, &place)) {
+            Some(error) => error,
+            None => invoke,
+        }
+    };
+
+    let mut invoke = match try_catch.source {
+        Source::Try(try_block) => {
+            let try_block = *try_block;
+            let block = try_block.block;
+            let resources = try_block.resources;
+            let mut block = if resources.is_empty() {
+                block.into_token_stream()
+            } else {
+                quote![{ #(#resources)* #block }]
+            };
+            if !try_block.attrs.is_empty() {
+                let attrs = try_block.attrs;
+                let value = Ident::new("__try_catch_try_value", Span::mixed_site());
+                block = quote![{
+                    #(#attrs)*
+                    let #value = #block;
+                    #value
+                }];
+            }
+            let mut invoke = match try_block.timeout {
+                Some(Timeout { duration, sleep }) => {
+                    let sleep = sleep.map_or_else(default_sleep, |sleep| Some(quote![(#sleep)]));
+                    if let Some(error) = leaves_block(block.clone(), "an async block") {
+                        return error;
+                    }
+                    let future = future_block(block);
+                    let deadline = Ident::new("__try_catch_deadline", Span::mixed_site());
+                    quote![{
+                        let #deadline: ::core::time::Duration = #duration;
+                        match ::try_catch::__private::timeout(
+                            #deadline,
+                            #future,
+                            #sleep(#deadline),
+                        ).await {
+                            ::core::result::Result::Ok(#result) => #result,
+                            ::core::result::Result::Err(#result_err) => {
+                                ::core::result::Result::Err(<#carrier as ::core::convert::From<_>>::from(#result_err))
+                            }
+                        }
+                    }]
+                }
+                None => invoke_block(block),
+            };
+            for fallback in try_block.fallbacks {
+                let error = match fallback.error {
+                    Some(error) => error.into_token_stream(),
+                    None => quote![_],
+                };
+                let fallback = invoke_block(fallback.block.into_token_stream());
+                let failed = take_variant(&result, false);
+                invoke = quote![{
+                    let #result: #result_type = #invoke;
+                    match #result {
+                        #result @ ::core::result::Result::Err(_) => {
+                            let #error = #failed;
+                            #fallback
+                        }
+                        #result => #result,
+                    }
+                }];
+            }
+
+            match try_block.retry {
+                Some(Retry::Count(retries)) => {
+                    let remaining = Ident::new("__try_catch_retries", Span::mixed_site());
+                    invoke = quote![{
+                        let mut #remaining: u32 = #retries;
+                        loop {
+                            match #invoke {
+                                ::core::result::Result::Err(_) if #remaining > 0 => #remaining -= 1,
+                                #result => break #result,
+                            }
+                        }
+                    }];
+                }
+                Some(Retry::Policy { policy, sleep }) => {
+                    let policy_var = Ident::new("__try_catch_policy", Span::mixed_site());
+                    let attempt = Ident::new("__try_catch_attempt", Span::mixed_site());
+                    let delay = Ident::new("__try_catch_delay", Span::mixed_site());
+                    let sleep =
+                        match sleep.map_or_else(default_sleep, |sleep| Some(quote![(#sleep)])) {
+                            Some(sleep) if is_async => quote![#sleep(#delay).await],
+                            _ => quote![::std::thread::sleep(#delay)],
+                        };
+                    // The failed attempt is dropped before sleeping, so that
+                    // its error is not held across the await.
+                    invoke = quote![{
+                        let mut #policy_var = #policy;
+                        let mut #attempt: u32 = 0;
+                        loop {
+                            let #delay = {
+                                let #result: #result_type = #invoke;
+                                match &#result {
+                                    ::core::result::Result::Err(#result_err) => {
+                                        #attempt += 1;
+                                        match ::try_catch::RetryPolicy::delay(&mut #policy_var, #attempt, &**#result_err) {
+                                            ::core::option::Option::Some(#delay) => #delay,
+                                            ::core::option::Option::None => break #result,
+                                        }
+                                    }
+                                    ::core::result::Result::Ok(_) => break #result,
+                                }
+                            };
+                            #sleep;
+                        }
+                    }];
+                }
+                None => {}
+            }
+            invoke
+        }
+        Source::Result(result) if propagating => quote![
+            ::try_catch::__private::propagate(#result)
+        ],
+        Source::Result(result) => quote![
+            ::core::result::Result::map_err(#result, ::core::convert::Into::into)
+        ],
+    };
+
+    if try_catch.catches.iter().any(|catch| catch.retry.is_some()) {
+        let mut counters = quote!();
+        let mut retry_arms = quote!();
+        for (i, catch) in try_catch.catches.iter().enumerate() {
+            if catch.by_ref.is_some() {
+                continue;
+            }
+            let on_match = match catch.retry {
+                Some(retries) => {
+                    let remaining =
+                        Ident::new(&format!("__try_catch_retries_{}", i), Span::mixed_site());
+                    counters.extend(quote![let mut #remaining: u32 = #retries;]);
+                    quote![if #remaining > 0 { #remaining -= 1 } else { break #result }]
+                }
+                None => quote![break #result],
+            };
+            let attrs = &catch.attrs;
+            retry_arms.extend(
+                match arm_guard(&result_err.to_token_stream(), catch, custom_carrier) {
+                    Some(guard) => quote![
+                        #(#attrs)*
+                        ::core::result::Result::Err(#result_err) if #guard => #on_match,
+                    ],
+                    None => quote![#(#attrs)* ::core::result::Result::Err(_) => #on_match,],
+                },
+            );
+        }
+        invoke = quote![{
+            #counters
+            loop {
+                let #result: #result_type = #invoke;
+                match &#result {
+                    #retry_arms
+                    _ => break #result,
+                }
+            }
+        }];
+    }
+
+    let mut template = match try_catch.on_cancel {
+        Some(on_cancel) => {
+            let guard = Ident::new("__try_catch_on_cancel", Span::mixed_site());
+            quote![
+                let #guard = ::try_catch::Deferred::new(|| #on_cancel);
+                let #result: #result_type = #invoke;
+                ::try_catch::Deferred::defuse(#guard);
+            ]
+        }
+        None => quote![
+            let #result: #result_type = #invoke;
+        ],
+    };
+    match try_catch.cleanup {
+        Some(cleanup) if try_exits => {
+            let guard = Ident::new("__try_catch_cleanup", Span::mixed_site());
+            if let Some(error) = awaits_in_guard(&cleanup, "cleanup") {
+                return error;
+            }
+            template = quote![
+                let #guard = ::try_catch::Deferred::new(|| { #cleanup; });
+                #template
+                ::core::mem::drop(#guard);
+            ];
+        }
+        Some(cleanup) => template.extend(quote![#cleanup;]),
+        None => {}
+    }
+
+    let expr = match try_catch.error_enum {
+        Some(error_enum) => {
+            let arms = error_enum.catches.into_iter().map(|catch| {
+                let VariantCatch {
+                    attrs,
+                    pat,
+                    guard,
+                    body,
+                } = catch;
+                let guard = guard.map(|guard| quote![if #guard]);
+                quote![#(#attrs)* #pat #guard => #body,]
+            });
+            let success = match try_catch.else_clause {
+                Some(Else { value, block }) => quote![
+                    let #value = #result_ok;
+                    #block
+                ],
+                None => quote![#result_ok],
+            };
+            quote!({
+                #template
+                match #result {
+                    ::core::result::Result::Ok(#result_ok) => { #success }
+                    ::core::result::Result::Err(#result_err) => match #result_err { #(#arms)* }
+                }
+            })
+        }
+        None => {
+            let handled = is_handled(&try_catch.catches, &try_catch.into, &try_catch.unmatched);
+            let success = match try_catch.else_clause {
+                Some(Else { value, block }) if handled => quote![
+                    let #value = #result_ok;
+                    #block
+                ],
+                Some(Else { value, block }) => {
+                    let ok = quote_spanned![block.span()=> ::core::result::Result::Ok(#block)];
+                    quote![
+                        let #value = #result_ok;
+                        #ok
+                    ]
+                }
+                None if handled => {
+                    let value =
+                        Ident::new("__try_catch_ok", Span::mixed_site().located_at(value_span));
+                    quote![#value]
+                }
+                None => quote_spanned![value_span=> ::core::result::Result::Ok(#result_ok)],
+            };
+            // The wrappers of the errors that no arm handles, innermost first.
+            let mut wrappers = vec![];
+            if let Some(context) = try_catch.context {
+                wrappers.push(quote![
+                    ::try_catch::ContextError::new(#context, #result_err)
+                ]);
+            }
+            // Spanned at the invocation, which is the location the error is given.
+            if try_catch.located.is_some() {
+                wrappers.push(quote![
+                    ::try_catch::Located::new(::core::panic::Location::caller(), #result_err)
+                ]);
+            }
+            boxed_catches(
+                try_catch.catches,
+                wrappers,
+                success,
+                try_catch.unmatched,
+                try_catch.into,
+                Carrying {
+                    custom_carrier,
+                    propagating,
+                    traced: try_catch.traced.is_some(),
+                },
+                template,
+            )
+        }
+    };
+
+    let value = Ident::new("__try_catch_value", Span::mixed_site());
+    let expr = match try_catch.finally {
+        Some(finally) if try_exits || arms_exit || propagates => {
+            if let Some(error) = awaits_in_guard(&finally, "finally") {
+                return error;
+            }
+            let guard = Ident::new("__try_catch_finally", Span::mixed_site());
+            quote!({
+                let #guard = ::try_catch::Deferred::new(|| { #finally; });
+                let #value = #expr;
+                #value
+            })
+        }
+        Some(finally) => {
+            quote!({
+                let #value = #expr;
+                #finally;
+                #value
+            })
+        }
+        None => expr,
+    };
+
+    let expr = if warnings.is_empty() && assertions.is_empty() {
+        expr
+    } else {
+        quote!({
+            #(#warnings)*
+            #(#assertions)*
+            #expr
+        })
+    };
+
+    if try_catch.lazy {
+        quote![async #capture #expr]
+    } else {
+        expr
+    }
+}
+
+/// Builds the arms that downcast the boxed error, or the error carrier that
+/// replaces the box, to the type of each catch arm. `success` is the value of
+/// the expression when the try block succeeded, and `wrappers` are the errors
+/// that the errors no arm handles are wrapped in, built from
+/// `__try_catch_error`.
+fn boxed_catches(
+    catches: Vec<Catch>,
+    wrappers: Vec<TokenStream2>,
+    success: TokenStream2,
+    unmatched: Unmatched,
+    into: Option<Type>,
+    carrying: Carrying,
+    mut template: TokenStream2,
+) -> TokenStream2 {
+    let Carrying {
+        custom_carrier,
+        propagating,
+        traced,
+    } = carrying;
+    let result = Ident::new("__try_catch_block", Span::mixed_site());
+    let result_err = Ident::new("__try_catch_error", Span::mixed_site());
+    let result_ok = Ident::new("__try_catch_ok", Span::mixed_site());
+    // Arms catching by reference are paired with `true`: they run and then
+    // leave the error to the arms after them.
+    let mut arms: Vec<(bool, TokenStream2)> = vec![];
+    // The bodies of the other arms, by the index their guard picks them with.
+    let mut handlers: Vec<TokenStream2> = vec![];
+    // Every error is handled, so the arms produce the value of the expression
+    // rather than a `Result`.
+    let handled = is_handled(&catches, &into, &unmatched);
+    let arms_span = catches
+        .iter()
+        .find(|catch| catch.by_ref.is_none())
+        .map_or_else(Span::call_site, |catch| catch.body.span());
+    let error_mut = if catches.iter().any(|catch| catch.by_ref == Some(ByRef::Mut)) {
+        quote![mut]
+    } else {
+        quote![]
+    };
+    for catch in catches {
+        let guard = arm_guard(&quote![(&#result_err)], &catch, custom_carrier);
+        // The guard may be the only place where the binding is used, while a
+        // binding used nowhere is still reported at the arm.
+        let allow_unused = match (&catch.error, &catch.guard) {
+            (Some(Pat::Ident(pattern)), Some(guard))
+                if mentions(guard.to_token_stream(), &pattern.ident) =>
+            {
+                Some(quote![#[allow(unused_variables)]])
+            }
+            _ => None,
+        };
+        let attrs = catch.attrs;
+        let expr = catch.body;
+        // A mismatch between the arms is reported at the arm that differs.
+        let span = expr.span();
+        let body = if catch.rethrows {
+            let label = arm_label();
+            let value = Ident::new("__try_catch_value", Span::mixed_site());
+            // The body may always rethrow, leaving the `Ok` unreachable.
+            quote![{
+                #[allow(unreachable_code, clippy::diverging_sub_expression)]
+                let #value = #label: { ::core::result::Result::Ok(#expr) };
+                #value
+            }]
+        } else if let Some(into) = &into {
+            quote_spanned![span=> ::core::result::Result::Err::<_, #into>(#expr)]
+        } else if handled {
+            expr.to_token_stream()
+        } else {
+            quote_spanned![span=> ::core::result::Result::Ok(#expr)]
+        };
+        let error_name = catch.error;
+        if let Some(by_ref) = catch.by_ref {
+            let guard = guard.map(|guard| quote![if #guard]);
+            let error = match (&catch.err_types[..], by_ref) {
+                ([ErrType { ty, .. }], ByRef::Shared) => downcast_ref(&quote![(&#result_err)], ty),
+                ([ErrType { ty, .. }], ByRef::Mut) => downcast_mut(&quote![#result_err], ty),
+                (_, ByRef::Shared) => quote![&#result_err],
+                (_, ByRef::Mut) => quote![&mut #result_err],
+            };
+            let binding = match error_name {
+                Some(Pat::Wild(_)) => quote![],
+                _ => quote![#allow_unused let #error_name = #error;],
+            };
+            arms.push((
+                true,
+                quote![
+                    match () {
+                        #(#attrs)*
+                        _ #guard => {
+                            #binding
+                            #expr;
+                        }
+                        _ => {}
+                    }
+                ],
+            ));
+        } else if !catch.err_types.is_empty() {
+            // An arm catching several types binds the boxed error.
+            let error = match &catch.err_types[..] {
+                // Trait objects can only be borrowed from the box.
+                [ErrType {
+                    ty: ty @ Type::TraitObject(_),
+                    ..
+                }] => downcast_ref(&quote![(&#result_err)], ty),
+                // `deep` arms borrow the error they find in the chain.
+                [ErrType { ty, .. }] if catch.deep => {
+                    let found = find_source(&quote![(&#result_err)], ty);
+                    quote![::try_catch::__private::found(#found)]
+                }
+                // Carriers such as `anyhow::Error` downcast to the error itself.
+                [ErrType { ty, .. }] if custom_carrier => quote![
+                    ::try_catch::__private::downcast(#result_err.downcast::<#ty>())
+                ],
+                [ErrType { ty, .. }] => {
+                    quote_spanned![ty.span()=> ::try_catch::__private::unbox::<#ty>(#result_err)]
+                }
+                _ => quote![#result_err],
+            };
+            let handle = match error_name {
+                Some(pattern) if !is_binding(&pattern) => {
+                    let value = Ident::new("__try_catch_value", Span::mixed_site());
+                    quote![
+                        #[allow(unreachable_patterns)]
+                        let #value = match #error {
+                            #pattern => #body,
+                            _ => ::try_catch::__private::matched(),
+                        };
+                        #value
+                    ]
+                }
+                // Rethrows in arms that borrow the error still refer to it.
+                Some(Pat::Wild(_)) if catch.rethrows => body,
+                Some(Pat::Wild(_)) => quote![
+                    ::core::mem::drop(#result_err);
+                    #body
+                ],
+                Some(error_name) => quote![
+                    #allow_unused
+                    let #error_name = #error;
+                    #body
+                ],
+                None if into.is_some() => quote_spanned![span=>
+                    ::core::result::Result::Err((#expr)(#error))
+                ],
+                None if handled => quote_spanned![span=> (#expr)(#error)],
+                None => quote_spanned![span=>
+                    ::core::result::Result::Ok((#expr)(#error))
+                ],
+            };
+            let index = Literal::usize_unsuffixed(handlers.len());
+            arms.push((false, quote![#(#attrs)* _ if #guard => #index,]));
+            handlers.push(quote![#(#attrs)* #index => { #handle }]);
+        } else {
+            let guard = guard.map(|guard| quote![if #guard]);
+            // The error is dropped rather than held across the awaits of the arm.
+            let binding = match error_name {
+                Some(Pat::Wild(_)) => quote![::core::mem::drop(#result_err);],
+                _ => {
+                    let error = trace(&result_err, traced);
+                    quote![#allow_unused let #error_name = #error;]
+                }
+            };
+            let index = Literal::usize_unsuffixed(handlers.len());
+            arms.push((false, quote![#(#attrs)* _ #guard => #index,]));
+            handlers.push(quote![#(#attrs)* #index => { #binding #body }]);
+        }
+    }
+
+    // The wildcard arm that `into` requires leaves no error to this arm.
+    let mut catch_template = match &into {
+        _ if handled => quote![],
+        Some(_) => quote![_ => ::try_catch::__private::handled()],
+        None => {
+            let index = Literal::usize_unsuffixed(handlers.len());
+            let error = trace(&result_err, traced);
+            handlers.push(quote![_ => ::core::result::Result::Err(#error)]);
+            quote![_ => #index,]
+        }
+    };
+    if handled || into.is_some() {
+        handlers.push(quote![_ => ::try_catch::__private::handled()]);
+    }
+    for (by_ref, arm) in arms.into_iter().rev() {
+        catch_template = if by_ref {
+            quote![_ => {
+                #arm
+                match () { #catch_template }
+            }]
+        } else {
+            quote![#arm #catch_template]
+        };
+    }
+
+    // The guards pick the arm in a scope of their own, since the error they
+    // borrow would otherwise be held across the awaits of the arm that runs.
+    // A mismatch between the arms and the try block is reported at the first arm.
+    let index = Ident::new("__try_catch_arm", Span::mixed_site());
+    let failed = take_variant(&result, false);
+    let mut catches = quote_spanned![arms_span=> {
+        let (#index, #result_err): (usize, _) = {
+            let #error_mut #result_err = #failed;
+            (match () { #catch_template }, #result_err)
+        };
+        match #index { #(#handlers)* }
+    }];
+    if !handled && propagating && !wrappers.is_empty() {
+        // The outermost wrapper is the error that the function returns, and
+        // the others are boxed in it.
+        let boxed = Ident::new("__try_catch_boxed", Span::mixed_site());
+        let outermost = wrappers.len() - 1;
+        let wrapped = wrappers.iter().enumerate().map(|(i, wrapper)| {
+            if i == outermost {
+                quote![::core::convert::From::from(#wrapper)]
+            } else {
+                quote![::try_catch::__private::Box::new(#wrapper)]
+            }
+        });
+        catches = quote![
+            match #catches {
+                ::core::result::Result::Err(#boxed) => {
+                    let #result_err: ::try_catch::__private::Box<dyn ::core::error::Error> =
+                        ::try_catch::__private::Propagating::into_box(#boxed);
+                    #(let #result_err = #wrapped;)*
+                    ::core::result::Result::Err(#result_err)
+                }
+                #result => #result,
+            }
+        ];
+    } else if !handled {
+        for wrapper in wrappers {
+            catches = quote![
+                match #catches {
+                    ::core::result::Result::Err(#result_err) => ::core::result::Result::Err(
+                        ::try_catch::__private::Box::new(#wrapper)
+                            as ::try_catch::__private::Box<dyn ::core::error::Error>
+                    ),
+                    #result => #result,
+                }
+            ];
+        }
+    }
+
+    // The result is moved out of as a whole, so that the arms and the success
+    // block do not hold on to it, and to the box in it, across their awaits.
+    let succeeded = take_variant(&result, true);
+    template.extend(quote![
+        match #result {
+            #result @ ::core::result::Result::Ok(_) => {
+                let #result_ok = #succeeded;
+                #success
+            }
+            #result @ ::core::result::Result::Err(_) => #catches
+        }
+    ]);
+
+    match unmatched {
+        _ if handled => quote!({#template}),
+        Unmatched::Propagate(_) if propagating => quote!(match {#template} {
+            ::core::result::Result::Ok(#result_ok) => #result_ok,
+            ::core::result::Result::Err(#result_err) => {
+                return ::core::result::Result::Err(
+                    ::try_catch::__private::Propagating::into_error(#result_err),
+                )
+            }
+        }),
+        Unmatched::Propagate(_) => quote!(match {#template} {
+            ::core::result::Result::Ok(#result_ok) => #result_ok,
+            ::core::result::Result::Err(#result_err) => {
+                return ::core::result::Result::Err(::core::convert::From::from(#result_err))
+            }
+        }),
+        Unmatched::Panic(message) => quote!(match {#template} {
+            ::core::result::Result::Ok(#result_ok) => #result_ok,
+            ::core::result::Result::Err(#result_err) => {
+                ::try_catch::__private::unhandled(#message, &*#result_err)
+            }
+        }),
+        Unmatched::Default => quote!(match {#template} {
+            ::core::result::Result::Ok(#result_ok) => #result_ok,
+            ::core::result::Result::Err(_) => ::core::default::Default::default(),
+        }),
+        Unmatched::Return | Unmatched::Result => quote!({#template}),
+    }
+}
+
+/// How errors are carried through the catch arms.
+#[derive(Clone, Copy)]
+struct Carrying {
+    /// Carried in a type such as `anyhow::Error` rather than a box.
+    custom_carrier: bool,
+    /// Carried in a `Propagating` for `propagate`.
+    propagating: bool,
+    /// Traced for the wildcard arm or the caller, with `traced`.
+    traced: bool,
+}
+
+/// The boxed error in `error`, traced for the wildcard arm or the caller with
+/// `traced`.
+fn trace(error: &Ident, traced: bool) -> TokenStream2 {
+    if traced {
+        quote![::try_catch::__private::Trace::trace(#error)]
+    } else {
+        error.to_token_stream()
+    }
+}
+
+/// Reports an `.await` in `clause`, the `cleanup` or `finally` block named
+/// `name`, when it has to run from a drop guard.
+fn awaits_in_guard(clause: &ExprBlock, name: &str) -> Option<TokenStream2> {
+    if !is_async(clause.block.to_token_stream()) {
+        return None;
+    }
+    let message = format!(
+        "`{}` cannot `.await` when the expression can be left early, \
+         as with `return`, since it then runs when the expression is dropped",
+        name
+    );
+    Some(Error::new(clause.span(), message).to_compile_error())
+}
+
+/// Reports the first `return`, `break` or `continue` of `block` that would
+/// leave it, since they would only leave the closure or the async block it
+/// runs in, given by `place`, instead of the code around the macro.
+fn leaves_block(block: TokenStream2, place: &str) -> Option<TokenStream2> {
+    let (span, keyword) = find_exit(block, false)?;
+    let message = format!(
+        "`{}` cannot leave this try block, which runs in {}",
+        keyword, place
+    );
+    Some(Error::new(span, message).to_compile_error())
+}
+
+/// Takes the value out of `result`, a `Result` known to be `Ok` if `ok` is
+/// true and `Err` otherwise.
+fn take_variant(result: &Ident, ok: bool) -> TokenStream2 {
+    if ok {
+        quote![::try_catch::__private::take_ok(#result)]
+    } else {
+        quote![::try_catch::__private::take_err(#result)]
+    }
+}
+
+/// A statement that makes rustc warn with `message` at `span`, as the use of a
+/// deprecated constant, since proc macros cannot emit warnings on stable.
+fn warning(span: Span, message: &str) -> TokenStream2 {
+    let name = Ident::new("__try_catch_warning", Span::mixed_site());
+    let usage = Ident::new("__try_catch_warning", Span::mixed_site().located_at(span));
+    quote![{
+        #[deprecated(note = #message)]
+        #[allow(non_upper_case_globals)]
+        const #name: () = ();
+        let () = #usage;
+    }]
+}
+
+/// Gives the braces of the user's blocks the hygiene of the macro, keeping
+/// their location. Braces around a single expression are otherwise reported
+/// by `unused_braces` wherever the expansion places the block as a value.
+fn own_braces(try_catch: &mut TryCatch) {
+    fn own(block: &mut ExprBlock) {
+        let span = &mut block.block.brace_token.span;
+        *span = Span::mixed_site().located_at(*span);
+    }
+    fn own_expr(expr: &mut Expr) {
+        if let Expr::Block(block) = expr {
+            own(block);
+        }
+    }
+    if let Source::Try(try_block) = &mut try_catch.source {
+        own(&mut try_block.block);
+        for fallback in &mut try_block.fallbacks {
+            own(&mut fallback.block);
+        }
+    }
+    if let Some(else_clause) = &mut try_catch.else_clause {
+        own(&mut else_clause.block);
+    }
+    for catch in &mut try_catch.catches {
+        own_expr(&mut catch.body);
+    }
+    if let Some(error_enum) = &mut try_catch.error_enum {
+        for catch in &mut error_enum.catches {
+            own_expr(&mut catch.body);
+        }
+    }
+}
+
+/// Rewrites each `?` of the try blocks so that a `None` becomes a `NoneError`,
+/// and its error goes through the `map_err` of the block, and, when errors are
+/// boxed, through `raise`, which
+/// requires it to be `'static` where the `?` is written, and boxes strings as
+/// `DisplayError`s that arms can catch. A borrowed error is otherwise only
+/// rejected by the conversion into the box, which the borrow checker blames on
+/// the whole invocation.
+///
+/// `enum` invocations keep their `?`s as written, since they must expand
+/// without the `alloc` feature that `__private` needs.
+fn convert_errors(try_catch: &mut TryCatch, propagating: bool) {
+    struct Converter {
+        map_err: Option<Expr>,
+        /// Whether a `None` becomes a `NoneError`, which needs `alloc`.
+        fallible: bool,
+        /// The box that errors are carried in, if they are boxed.
+        boxed: Option<Type>,
+        /// Whether errors are carried with their conversion for `propagate`.
+        propagating: bool,
+    }
+    impl VisitMut for Converter {
+        fn visit_expr_mut(&mut self, expr: &mut Expr) {
+            match expr {
+                // These have a `?` of their own.
+                Expr::Closure(_) | Expr::Async(_) | Expr::TryBlock(_) => {}
+                Expr::Try(ExprTry { expr: value, .. }) => {
+                    self.visit_expr_mut(value);
+                    let span = value.span();
+                    if self.fallible {
+                        **value =
+                            parse_quote_spanned![span=> ::try_catch::__private::fallible(#value)];
+                    }
+                    if let Some(map_err) = &self.map_err {
+                        **value = parse_quote_spanned![span=>
+                            ::core::result::Result::map_err(#value, #map_err)
+                        ];
+                    }
+                    if self.propagating {
+                        **value = parse_quote_spanned![span=>
+                            ::try_catch::__private::propagate(#value)
+                        ];
+                    } else if let Some(carrier) = &self.boxed {
+                        **value = parse_quote_spanned![span=>
+                            ::try_catch::__private::raise::<#carrier, _, _, _>(#value)
+                        ];
+                    }
+                }
+                _ => visit_mut::visit_expr_mut(self, expr),
+            }
+        }
+
+        // Items are not part of the block.
+        fn visit_item_mut(&mut self, _: &mut Item) {}
+    }
+    let boxed = match &try_catch.carrier {
+        _ if try_catch.error_enum.is_some() => None,
+        Some(carrier) if !is_box(carrier) => None,
+        Some(carrier) => Some(carrier.clone()),
+        None => Some(parse_quote![
+            ::try_catch::__private::Box<dyn ::core::error::Error>
+        ]),
+    };
+    let Source::Try(try_block) = &mut try_catch.source else {
+        return;
+    };
+    let mut converter = Converter {
+        map_err: try_block.map_err.take(),
+        fallible: try_catch.error_enum.is_none(),
+        boxed,
+        propagating,
+    };
+    converter.visit_expr_block_mut(&mut try_block.block);
+    for fallback in &mut try_block.fallbacks {
+        converter.visit_expr_block_mut(&mut fallback.block);
+    }
+}
+
+/// Whether the arms handle every error: there is a wildcard arm, and no arm
+/// rethrows the error or translates it with `into`. `catch_expr!` keeps the
+/// `Result` regardless.
+fn is_handled(catches: &[Catch], into: &Option<Type>, unmatched: &Unmatched) -> bool {
+    into.is_none()
+        && !matches!(unmatched, Unmatched::Result)
+        && !catches.iter().any(|catch| catch.rethrows)
+        && catches.iter().any(TryCatch::is_wildcard)
+}
+
+/// The guard that tells whether the error behind the `&Box<dyn Error>` in `error`
+/// is handled by `catch`, or `None` if the arm catches everything.
+///
+/// Each arm checks the type with `is::<T>()`. Dispatching on the `TypeId` of
+/// the error once would save the checks of the arms before the matching one,
+/// but the `TypeId` behind a `dyn Error` is only reachable through the
+/// unstable `Error::type_id`.
+fn arm_guard(error: &TokenStream2, catch: &Catch, custom_carrier: bool) -> Option<TokenStream2> {
+    let kind_error = Ident::new("__try_catch_kind_error", Span::mixed_site());
+    let mut checks: Vec<_> = catch
+        .err_types
+        .iter()
+        .map(|ErrType { ty, kinds }| {
+            if let Type::TraitObject(_) = ty {
+                return quote![
+                    <#ty as ::try_catch::ErrorCast>::cast(
+                        ::try_catch::__private::untraced(&**#error),
+                    ).is_some()
+                ];
+            }
+            // Carriers such as `anyhow::Error` have downcasts of their own,
+            // while boxes may hold an error traced by another `catch!`.
+            if kinds.is_empty() && !catch.deep {
+                return if custom_carrier {
+                    quote![#error.is::<#ty>()]
+                } else {
+                    quote_spanned![ty.span()=> ::try_catch::__private::is::<#ty>(&**#error)]
+                };
+            }
+            let found = if catch.deep {
+                find_source(error, ty)
+            } else if custom_carrier {
+                quote![#error.downcast_ref::<#ty>()]
+            } else {
+                quote_spanned![ty.span()=> ::try_catch::__private::downcast_ref::<#ty>(&**#error)]
+            };
+            if kinds.is_empty() {
+                return quote![#found.is_some()];
+            }
+            quote![
+                #found
+                    .is_some_and(|#kind_error| ::core::matches!(#kind_error.kind(), #(#kinds)|*))
+            ]
+        })
+        .collect();
+    if checks.len() > 1 {
+        checks = vec![quote![(#(#checks)||*)]];
+    }
+    // The pattern and the guard see the error by reference, so a failed
+    // match leaves it to the next arms.
+    let binding = match &catch.err_types[..] {
+        [ErrType { ty, .. }] if catch.deep => {
+            let found = find_source(error, ty);
+            quote![::try_catch::__private::found(#found)]
+        }
+        [ErrType { ty, .. }] => downcast_ref(error, ty),
+        _ => quote![#error],
+    };
+    match (&catch.error, &catch.guard) {
+        (Some(pattern), guard) if !is_binding(pattern) => {
+            let matched = Ident::new("__try_catch_matched", Span::mixed_site());
+            let guard = match guard {
+                Some(guard) => guard.to_token_stream(),
+                None => quote![true],
+            };
+            checks.push(quote![{
+                #[allow(unreachable_patterns, unused_variables)]
+                let #matched = match #binding {
+                    #pattern => #guard,
+                    _ => false,
+                };
+                #matched
+            }]);
+        }
+        (Some(error_name), Some(guard)) => checks.push(quote![{
+            #[allow(unused_variables)]
+            let #error_name = #binding;
+            #guard
+        }]),
+        _ => {}
+    }
+    if checks.is_empty() {
+        None
+    } else {
+        Some(quote![#(#checks)&&*])
+    }
+}
+
+/// Whether `ident` appears anywhere in `tokens`, including in the macro calls
+/// they contain.
+fn mentions(tokens: TokenStream2, ident: &Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(other) => other == *ident,
+        TokenTree::Group(group) => mentions(group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Borrows the error behind `error`, a `Box<dyn Error>` or a reference to one,
+/// as a `&ty` once it is known to be one.
+fn downcast_ref(error: &TokenStream2, ty: &Type) -> TokenStream2 {
+    match ty {
+        Type::TraitObject(_) => quote![
+            ::try_catch::__private::found(<#ty as ::try_catch::ErrorCast>::cast(
+                ::try_catch::__private::untraced(&**#error),
+            ))
+        ],
+        _ => quote_spanned![ty.span()=> ::try_catch::__private::borrow::<#ty>(&**#error)],
+    }
+}
+
+/// Looks for an error of type `ty` in the `source()` chain of the error behind
+/// `error`, starting with the error itself.
+fn find_source(error: &TokenStream2, ty: &Type) -> TokenStream2 {
+    quote_spanned![ty.span()=> ::try_catch::__private::find_source::<#ty>(&**#error)]
+}
+
+/// Like [`downcast_ref`], but borrows the error mutably.
+fn downcast_mut(error: &TokenStream2, ty: &Type) -> TokenStream2 {
+    match ty {
+        Type::TraitObject(_) => quote![
+            ::try_catch::__private::found(<#ty as ::try_catch::ErrorCast>::cast_mut(
+                ::try_catch::__private::untraced_mut(&mut **#error),
+            ))
+        ],
+        _ => quote_spanned![ty.span()=> ::try_catch::__private::borrow_mut::<#ty>(&mut *#error)],
+    }
+}
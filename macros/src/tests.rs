@@ -0,0 +1,407 @@
+//! Expansion tests checking that the user's tokens keep their spans, which
+//! is what lets rust-analyzer resolve, complete and hover code inside the
+//! blocks.
+
+use crate::parse::TryCatch;
+use crate::template::template;
+use proc_macro2::{LineColumn, TokenStream as TokenStream2, TokenTree};
+
+/// The identifiers of `tokens`, with where they start.
+fn idents(tokens: TokenStream2) -> Vec<(String, LineColumn)> {
+    let mut idents = Vec::new();
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => idents.push((ident.to_string(), ident.span().start())),
+            TokenTree::Group(group) => idents.extend(self::idents(group.stream())),
+            _ => {}
+        }
+    }
+    idents
+}
+
+/// Expands `source` and checks that each of `names`, which must occur once
+/// in `source`, is emitted with the span it was written with.
+fn assert_spliced(source: &str, names: &[&str]) {
+    let input: TokenStream2 = source.parse().unwrap();
+    let written = idents(input.clone());
+    let try_catch: TryCatch = syn::parse2(input).unwrap();
+    let expanded = idents(template(try_catch));
+    for name in names {
+        let (_, at) = written
+            .iter()
+            .find(|(ident, _)| ident == name)
+            .unwrap_or_else(|| panic!("`{}` is not in the source", name));
+        assert!(
+            expanded
+                .iter()
+                .any(|(ident, start)| ident == name && start == at),
+            "`{}` at {}:{} lost its span",
+            name,
+            at.line,
+            at.column,
+        );
+    }
+}
+
+#[test]
+fn try_block() {
+    assert_spliced(
+        "
+        try {
+            let number: i32 = text.parse()?;
+            if number < 0 {
+                throw Negative;
+            }
+            number
+        }
+        catch _ => 0,
+        ",
+        &["number", "i32", "text", "parse", "Negative"],
+    );
+}
+
+#[test]
+fn single_expression() {
+    assert_spliced(
+        "
+        try text.parse::<i32>()?;
+        catch _ => 0,
+        ",
+        &["text", "parse", "i32"],
+    );
+}
+
+#[test]
+fn closure() {
+    assert_spliced(
+        "
+        move try { text.parse::<i32>()? }
+        catch _: ParseIntError { 1 }
+        ",
+        &["text", "parse", "ParseIntError"],
+    );
+}
+
+#[test]
+fn map_err() {
+    assert_spliced(
+        "
+        map_err = OwnedError::from try {
+            let value = parse(input)?;
+            throw Invalid(value);
+        }
+        catch _ => 0,
+        ",
+        &["OwnedError", "from", "value", "parse", "input", "Invalid"],
+    );
+}
+
+#[test]
+fn async_block() {
+    assert_spliced(
+        "
+        try { fetch(url).await? }
+        catch error: io::Error if error.kind() == kind { log(error) }
+        ",
+        &["fetch", "url", "io", "kind", "log"],
+    );
+}
+
+#[test]
+fn arms() {
+    assert_spliced(
+        "
+        try { text.parse::<i32>()? }
+        catch error: ParseIntError if strict {
+            report(&error);
+            fallback
+        }
+        catch &error: io::Error => logged(error),
+        catch _ => other,
+        finally { done() }
+        ",
+        &[
+            "ParseIntError",
+            "strict",
+            "report",
+            "fallback",
+            "logged",
+            "other",
+            "done",
+        ],
+    );
+}
+
+#[test]
+fn arm_syntax_error() {
+    let source = "
+        try { text.parse::<i32>()? }
+        catch _: ParseIntError => 1,
+        catch _: io::Error => 2,
+        catch error: io::Error if error.kind() == => 3,
+        catch _ => 4,
+    ";
+    let error = syn::parse_str::<TryCatch>(source).err().unwrap();
+    assert_eq!(error.span().start().line, 5);
+}
+
+#[test]
+fn arm_after_wildcard() {
+    let source = "
+        try { text.parse::<i32>()? }
+        catch error => 0,
+        catch error: ParseIntError => 1,
+    ";
+    let error = syn::parse_str::<TryCatch>(source).err().unwrap();
+    let lines: Vec<_> = error
+        .into_iter()
+        .map(|error| error.span().start().line)
+        .collect();
+    assert_eq!(lines, [4, 3]);
+}
+
+/// Expands `source` and checks that every `keyword` in it is left as a name
+/// rather than rewritten as a statement.
+fn assert_named(source: &str, keyword: &str) {
+    let input: TokenStream2 = source.parse().unwrap();
+    let written: Vec<_> = idents(input.clone())
+        .into_iter()
+        .filter(|(ident, _)| ident == keyword)
+        .collect();
+    assert!(!written.is_empty(), "`{}` is not in the source", keyword);
+    let try_catch: TryCatch = syn::parse2(input).unwrap();
+    let expanded = idents(template(try_catch));
+    for (_, at) in written {
+        assert!(
+            expanded
+                .iter()
+                .any(|(ident, start)| ident == keyword && *start == at),
+            "`{}` at {}:{} was rewritten",
+            keyword,
+            at.line,
+            at.column,
+        );
+    }
+}
+
+#[test]
+fn throw_as_a_name() {
+    // A method, a field and a path segment.
+    assert_named(
+        "
+        try {
+            let ball = game.throw()?;
+            let speed = ball.throw;
+            Ball::throw(speed)?
+        }
+        catch _ => 0,
+        ",
+        "throw",
+    );
+    // A pattern, and the arguments of a call.
+    assert_named(
+        "
+        try {
+            let throw = game.next()?;
+            score(throw, 1)?;
+            Ok(throw)
+        }
+        catch _ => 0,
+        ",
+        "throw",
+    );
+    // A function, and an assignment.
+    assert_named(
+        "
+        try {
+            let mut result = throw(ball);
+            throw = result?;
+            score(throw, 2)?
+        }
+        catch _ => 0,
+        ",
+        "throw",
+    );
+}
+
+#[test]
+fn arm_keywords_as_names() {
+    assert_named(
+        "
+        try { text.parse::<i32>()? }
+        catch error: ParseIntError {
+            let resume = cursor.resume;
+            cursor.resume(resume)
+        }
+        catch _ => session.resume(),
+        ",
+        "resume",
+    );
+    assert_named(
+        "
+        try { text.parse::<i32>()? }
+        catch error: ParseIntError {
+            let rethrow = policy.rethrow;
+            Policy::rethrow(rethrow)
+        }
+        catch _ => rethrow(1),
+        ",
+        "rethrow",
+    );
+}
+
+/// Expands `source` and checks that every `keyword` in it is rewritten, and
+/// that each of `names`, the code after it, keeps its span.
+fn assert_rewritten(source: &str, keyword: &str, names: &[&str]) {
+    let try_catch: TryCatch = syn::parse_str(source).unwrap_or_else(|error| panic!("{}", error));
+    assert!(
+        idents(template(try_catch))
+            .iter()
+            .all(|(ident, _)| ident != keyword),
+        "`{}` was left as a name",
+        keyword,
+    );
+    assert_spliced(source, names);
+}
+
+#[test]
+fn throw_in_match_arm() {
+    // The thrown value ends at the `,` before the next arm.
+    assert_rewritten(
+        "
+        try {
+            match code {
+                0 => throw io::Error::new(ErrorKind::Other, \"zero\"),
+                _ => fallback,
+            }
+        }
+        catch _ => 0,
+        ",
+        "throw",
+        &["ErrorKind", "fallback"],
+    );
+    // Or at the end of the match.
+    assert_rewritten(
+        "
+        try {
+            match code {
+                1 => fallback,
+                _ => throw Unknown
+            }
+        }
+        catch _ => 0,
+        ",
+        "throw",
+        &["fallback", "Unknown"],
+    );
+}
+
+#[test]
+fn resume_in_match_arm() {
+    assert_rewritten(
+        "
+        try { text.parse::<i32>()? }
+        catch error: ParseIntError {
+            match text.len() {
+                1 => resume 5,
+                _ => log(error),
+            }
+        }
+        ",
+        "resume",
+        &["log"],
+    );
+}
+
+#[test]
+fn rethrow_in_match_arm() {
+    // A bare `rethrow` ends at the `,` before the next arm.
+    assert_rewritten(
+        "
+        try { text.parse::<i32>()? }
+        catch error: ParseIntError {
+            match text.len() {
+                0 => rethrow,
+                _ => log(error),
+            }
+        }
+        ",
+        "rethrow",
+        &["log"],
+    );
+    assert_rewritten(
+        "
+        try { text.parse::<i32>()? }
+        catch error: ParseIntError {
+            match text.len() {
+                0 => rethrow with \"empty\",
+                _ => rethrow Invalid(error),
+            }
+        }
+        ",
+        "rethrow",
+        &["Invalid"],
+    );
+}
+
+/// Expands `source` and checks that the expansion panics on nothing of its
+/// own, through `unwrap` or `unreachable!`, which crates may deny.
+fn assert_no_panics(source: &str) {
+    let try_catch: TryCatch = syn::parse_str(source).unwrap();
+    let expanded = idents(template(try_catch));
+    for (ident, _) in expanded {
+        assert!(
+            !["unwrap", "expect", "unreachable", "panic"].contains(&ident.as_str()),
+            "the expansion of `{}` calls `{}`",
+            source.trim(),
+            ident,
+        );
+    }
+}
+
+#[test]
+fn no_panics() {
+    assert_no_panics(
+        "
+        try { text.parse::<u32>()? }
+        catch deep _: ParseIntError => 1,
+        catch error: dyn Retryable => error.attempts(),
+        catch message: String => message.len(),
+        catch _ => 0,
+        ",
+    );
+    assert_no_panics(
+        "
+        try { text.parse::<u32>()? }
+        catch &error: dyn Retryable => log(error),
+        catch error: io::Error(kind = NotFound) => 1,
+        ",
+    );
+    assert_no_panics(
+        "
+        error = anyhow::Error try { text.parse::<u32>()? }
+        catch error: ParseIntError => 1,
+        catch _ => 0,
+        ",
+    );
+    assert_no_panics(
+        "
+        into Failure try { text.parse::<u32>()? }
+        catch error: ParseIntError => Failure::Parse(error),
+        catch _ => Failure::Other,
+        ",
+    );
+    assert_no_panics(
+        "
+        propagate try { text.parse::<u32>()? }
+        catch _: ParseIntError if strict => 1,
+        ",
+    );
+    assert_no_panics(
+        "
+        enum LookupError try { lookup(key)?.parse::<i32>()? }
+        catch LookupError::NotFound { 0 }
+        catch LookupError::Parse(error) { -1 }
+        ",
+    );
+}
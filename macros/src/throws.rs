@@ -0,0 +1,177 @@
+//! `#[throws(..)]`, which makes a function return a `Result` whose errors are
+//! the ones it lists.
+
+use crate::prelude::*;
+
+use proc_macro2::Span;
+use quote::{quote_spanned, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit_mut::{self, VisitMut};
+
+/// The errors a function raises: either listed, and boxed, or the variants of
+/// an enum generated next to the function.
+enum Throws {
+    Listed(Punctuated<Type, Token![,]>),
+    Enum {
+        name: Ident,
+        variants: Punctuated<Variant, Token![,]>,
+    },
+}
+
+impl Parse for Throws {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if input.is_empty() {
+            return Err(Error::new(
+                Span::call_site(),
+                "expected the errors the function raises, as in `#[throws(io::Error)]`",
+            ));
+        }
+        if !input.peek(Token![enum]) {
+            return Ok(Throws::Listed(Punctuated::parse_terminated(input)?));
+        }
+        input.parse::<Token![enum]>()?;
+        let name = input.parse()?;
+        let content;
+        braced!(content in input);
+        let variants: Punctuated<Variant, Token![,]> = Punctuated::parse_terminated(&content)?;
+        for variant in &variants {
+            match &variant.fields {
+                Fields::Unnamed(fields)
+                    if fields.unnamed.len() == 1 && variant.discriminant.is_none() => {}
+                _ => {
+                    return Err(Error::new(
+                        variant.span(),
+                        "expected a variant holding the error, as in `Io(io::Error)`",
+                    ))
+                }
+            }
+        }
+        Ok(Throws::Enum { name, variants })
+    }
+}
+
+pub fn throws(attr: TokenStream2, item: TokenStream2) -> Result<TokenStream2> {
+    let throws: Throws = syn::parse2(attr)?;
+    let mut function: ItemFn = syn::parse2(item)?;
+    let (error, set, generated) = match &throws {
+        Throws::Listed(errors) => {
+            let error = quote![::try_catch::__private::Box<dyn ::core::error::Error>];
+            (error, Some(declared(errors)), None)
+        }
+        Throws::Enum { name, variants } => {
+            let generated = generate_enum(&function.vis, name, variants);
+            (name.to_token_stream(), None, Some(generated))
+        }
+    };
+    let value = match &function.sig.output {
+        ReturnType::Default => quote![()],
+        ReturnType::Type(_, ty) => ty.to_token_stream(),
+    };
+    let arrow = <Token![->]>::default();
+    function.sig.output = parse_quote![#arrow ::core::result::Result<#value, #error>];
+    Returns { set }.visit_block_mut(&mut function.block);
+    let block = &function.block;
+    *function.block = parse_quote!({ ::core::result::Result::Ok(#block) });
+    Ok(quote![#generated #function])
+}
+
+/// The errors listed in `#[throws(..)]` as the nested pairs of `Declared`.
+fn declared(errors: &Punctuated<Type, Token![,]>) -> TokenStream2 {
+    errors
+        .iter()
+        .rev()
+        .fold(quote![()], |rest, error| quote![(#error, #rest)])
+}
+
+/// Wraps the values of `return` in `Ok`, and checks that the errors of `?` are
+/// listed in `set`, if they are boxed.
+struct Returns {
+    set: Option<TokenStream2>,
+}
+
+impl VisitMut for Returns {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            // These have a `return` and `?` of their own.
+            Expr::Closure(_) | Expr::Async(_) | Expr::TryBlock(_) => {}
+            Expr::Return(ret) => {
+                let span = match &ret.expr {
+                    Some(value) => value.span(),
+                    None => ret.span(),
+                };
+                let value = match &mut ret.expr {
+                    Some(value) => {
+                        self.visit_expr_mut(value);
+                        value.to_token_stream()
+                    }
+                    None => quote_spanned![span=> ()],
+                };
+                *expr = parse_quote_spanned![span=>
+                    return ::core::result::Result::Ok(#value)
+                ];
+            }
+            Expr::Try(ExprTry { expr: value, .. }) => {
+                self.visit_expr_mut(value);
+                if let Some(set) = &self.set {
+                    let span = value.span();
+                    **value = parse_quote_spanned![span=>
+                        ::try_catch::__private::declared::<#set, _, _, _>(#value)
+                    ];
+                }
+            }
+            _ => visit_mut::visit_expr_mut(self, expr),
+        }
+    }
+
+    // Items are not part of the body.
+    fn visit_item_mut(&mut self, _: &mut Item) {}
+}
+
+/// The enum of `#[throws(enum Name { .. })]`, which displays as the error it
+/// holds and converts from each of them.
+fn generate_enum(
+    vis: &Visibility,
+    name: &Ident,
+    variants: &Punctuated<Variant, Token![,]>,
+) -> TokenStream2 {
+    let error = Ident::new("__try_catch_error", Span::mixed_site());
+    let names = variants.iter().map(|variant| &variant.ident);
+    let conversions = variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        let ty = &variant.fields.iter().next().unwrap().ty;
+        quote! {
+            impl ::core::convert::From<#ty> for #name {
+                fn from(#error: #ty) -> Self {
+                    #name::#ident(#error)
+                }
+            }
+        }
+    });
+    let display = names.clone();
+    quote! {
+        #[derive(Debug)]
+        #vis enum #name {
+            #variants
+        }
+
+        #(#conversions)*
+
+        impl ::core::fmt::Display for #name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match self {
+                    #(#name::#display(#error) => ::core::fmt::Display::fmt(#error, f),)*
+                }
+            }
+        }
+
+        impl ::core::error::Error for #name {
+            fn source(&self) -> ::core::option::Option<&(dyn ::core::error::Error + 'static)> {
+                match self {
+                    #(#name::#names(#error) => ::core::error::Error::source(#error),)*
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Checks that `enum` invocations of `catch!` expand in a `no_std` crate
+//! without an allocator, where `try-catch` has none of its features.
+//!
+//! Built on its own, as `cargo build -p try-catch-no-alloc`, since the rest of
+//! the workspace enables `alloc` for it otherwise.
+#![no_std]
+
+use core::num::ParseIntError;
+use try_catch::catch;
+
+pub enum LookupError {
+    NotFound,
+    Parse(ParseIntError),
+}
+
+impl From<ParseIntError> for LookupError {
+    fn from(error: ParseIntError) -> Self {
+        LookupError::Parse(error)
+    }
+}
+
+fn lookup(key: &str) -> Result<&'static str, LookupError> {
+    match key {
+        "answer" => Ok("42"),
+        "name" => Ok("forty-two"),
+        _ => Err(LookupError::NotFound),
+    }
+}
+
+/// Arms matching the variants of the error, with a `finally` block.
+/// ```rust
+/// assert_eq!(try_catch_no_alloc::number("answer"), 42);
+/// assert_eq!(try_catch_no_alloc::number("name"), -1);
+/// assert_eq!(try_catch_no_alloc::number("other"), 0);
+/// ```
+pub fn number(key: &str) -> i32 {
+    let mut lookups = 0;
+    let number = catch! {
+        enum LookupError try {
+            lookup(key)?.parse::<i32>()?
+        } catch LookupError::NotFound {
+            0
+        } catch LookupError::Parse(_) {
+            -1
+        } finally {
+            lookups += 1;
+        }
+    };
+    debug_assert_eq!(lookups, 1);
+    number
+}
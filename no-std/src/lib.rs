@@ -0,0 +1,79 @@
+//! Checks that `catch!` expands in a `no_std` crate that has an allocator.
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::num::ParseIntError;
+use try_catch::catch;
+
+#[derive(Debug)]
+pub struct Negative;
+
+impl fmt::Display for Negative {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("negative number")
+    }
+}
+
+impl core::error::Error for Negative {}
+
+/// Typed arms and a wildcard arm.
+/// ```rust
+/// assert_eq!(try_catch_no_std::parse("7"), 7);
+/// assert_eq!(try_catch_no_std::parse("seven"), 0);
+/// assert_eq!(try_catch_no_std::parse("-7"), -1);
+/// ```
+pub fn parse(text: &str) -> i32 {
+    catch! {
+        try {
+            let number: i32 = text.parse()?;
+            if number < 0 {
+                throw Negative;
+            }
+            number
+        }
+        catch _: ParseIntError => 0,
+        catch _: Negative => -1,
+        catch _ => i32::MIN,
+    }
+}
+
+/// Errors that no arm handles are returned boxed, and `rethrow with` wraps them.
+/// ```rust
+/// assert_eq!(try_catch_no_std::describe("7").unwrap(), "7");
+/// let error = try_catch_no_std::describe("-7").unwrap_err();
+/// assert_eq!(error.to_string(), "cannot describe -7");
+/// ```
+pub fn describe(text: &str) -> Result<String, alloc::boxed::Box<dyn core::error::Error>> {
+    catch! {
+        try {
+            let number: i32 = text.parse()?;
+            if number < 0 {
+                throw Negative;
+            }
+            number.to_string()
+        }
+        catch error: Negative {
+            rethrow with "cannot describe {text}";
+        }
+        catch &error: ParseIntError {
+            let _ = error;
+        }
+    }
+}
+
+/// `#[throws(..)]` boxes the errors it lists.
+/// ```rust
+/// assert_eq!(try_catch_no_std::declared("7").unwrap(), 7);
+/// assert!(try_catch_no_std::declared("-7").unwrap_err().is::<try_catch_no_std::Negative>());
+/// ```
+#[try_catch::throws(ParseIntError, Negative)]
+pub fn declared(text: &str) -> i32 {
+    let number: i32 = text.parse()?;
+    if number < 0 {
+        Err(Negative)?;
+    }
+    number
+}
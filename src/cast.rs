@@ -0,0 +1,61 @@
+use core::error::Error;
+
+/// Lets `catch e: dyn Trait` arms match every error implementing `Trait`.
+///
+/// A `dyn Error` can only be downcast to concrete types, so this is implemented
+/// on `dyn Trait` by trying each type that implements it, usually with
+/// [`error_cast!`](crate::error_cast).
+pub trait ErrorCast {
+    fn cast<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a Self>;
+    fn cast_mut<'a>(error: &'a mut (dyn Error + 'static)) -> Option<&'a mut Self>;
+}
+
+/// Implements [`ErrorCast`] for a trait object, given the error types that
+/// implement the trait.
+///
+/// ```rust
+/// # use std::{fmt, io};
+/// trait Retryable {
+///     fn attempts(&self) -> u32;
+/// }
+/// impl Retryable for io::Error {
+///     fn attempts(&self) -> u32 {
+///         3
+///     }
+/// }
+/// impl Retryable for fmt::Error {
+///     fn attempts(&self) -> u32 {
+///         1
+///     }
+/// }
+///
+/// try_catch::error_cast!(dyn Retryable: io::Error, fmt::Error);
+/// ```
+#[macro_export]
+macro_rules! error_cast {
+    (dyn $trait:path: $($ty:ty),+ $(,)?) => {
+        impl $crate::ErrorCast for dyn $trait {
+            fn cast<'a>(
+                error: &'a (dyn ::core::error::Error + 'static),
+            ) -> ::core::option::Option<&'a Self> {
+                $(
+                    if let ::core::option::Option::Some(error) = error.downcast_ref::<$ty>() {
+                        return ::core::option::Option::Some(error as &Self);
+                    }
+                )+
+                ::core::option::Option::None
+            }
+
+            fn cast_mut<'a>(
+                error: &'a mut (dyn ::core::error::Error + 'static),
+            ) -> ::core::option::Option<&'a mut Self> {
+                $(
+                    if error.is::<$ty>() {
+                        return error.downcast_mut::<$ty>().map(|error| error as &mut Self);
+                    }
+                )+
+                ::core::option::Option::None
+            }
+        }
+    };
+}
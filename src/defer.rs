@@ -0,0 +1,93 @@
+/// Calls a function when dropped, unless it was defused first.
+///
+/// Created by [`defer!`](crate::defer), which runs the function when its scope
+/// exits, and by [`defer_on_error!`](crate::defer_on_error), whose guard is
+/// defused once the scope has succeeded. The try block of an `on_cancel`
+/// clause is awaited under one, defused once it completes, so that the clause
+/// only runs when the future awaiting the try block is dropped before then,
+/// and `cleanup` and `finally` run from one when the expression can be left
+/// early.
+#[must_use = "the function runs as soon as the guard is dropped"]
+pub struct Deferred<F: FnOnce()>(Option<F>);
+
+impl<F: FnOnce()> Deferred<F> {
+    pub fn new(deferred: F) -> Self {
+        Deferred(Some(deferred))
+    }
+
+    /// Drops the guard without calling the function.
+    pub fn defuse(mut self) {
+        self.0 = None;
+    }
+}
+
+impl<F: FnOnce()> Drop for Deferred<F> {
+    fn drop(&mut self) {
+        if let Some(deferred) = self.0.take() {
+            deferred();
+        }
+    }
+}
+
+/// Runs the given statements when the enclosing scope exits, whether it
+/// completes, returns early, raises an error with `?` or panics.
+///
+/// The statements borrow what they use until then, and deferred statements
+/// run in the reverse order of the `defer!`s, as values are dropped.
+///
+/// ```rust
+/// # use try_catch::{catch, defer};
+/// # use std::cell::RefCell;
+/// let log = RefCell::new(vec![]);
+/// let number: i32 = catch! {
+///     try {
+///         defer! { log.borrow_mut().push("closed"); }
+///         log.borrow_mut().push("opened");
+///         "ten".parse::<i32>()?
+///     } catch error {
+///         log.borrow_mut().push("caught");
+///         0
+///     }
+/// };
+/// assert_eq!(number, 0);
+/// assert_eq!(*log.borrow(), ["opened", "closed", "caught"]);
+/// ```
+#[macro_export]
+macro_rules! defer {
+    ($($deferred:tt)*) => {
+        let _deferred = $crate::Deferred::new(|| { $($deferred)* });
+    };
+}
+
+/// Returns a [`Deferred`] guard running the given statements when dropped,
+/// unless it is defused first.
+///
+/// Defusing the guard once the scope has succeeded leaves the statements to
+/// the paths where it did not: an error raised with `?`, an early return or a
+/// panic. The guard must be bound to a variable, since it runs the statements
+/// as soon as it is dropped.
+///
+/// ```rust
+/// # use try_catch::{catch, defer_on_error};
+/// # use std::{cell::RefCell, num::ParseIntError};
+/// fn import(rows: &RefCell<Vec<i32>>, text: &str) -> Result<(), ParseIntError> {
+///     let len = rows.borrow().len();
+///     let rollback = defer_on_error! { rows.borrow_mut().truncate(len); };
+///     for row in text.split(',') {
+///         rows.borrow_mut().push(row.parse()?);
+///     }
+///     rollback.defuse();
+///     Ok(())
+/// }
+///
+/// let rows = RefCell::new(vec![]);
+/// import(&rows, "1,2").unwrap();
+/// import(&rows, "3,four").unwrap_err();
+/// assert_eq!(*rows.borrow(), [1, 2]);
+/// ```
+#[macro_export]
+macro_rules! defer_on_error {
+    ($($deferred:tt)*) => {
+        $crate::Deferred::new(|| { $($deferred)* })
+    };
+}
@@ -0,0 +1,154 @@
+//! The downcasts that typed catch arms expand to, once their guard has checked
+//! the type of the error. Keeping them here compiles them once per error type,
+//! rather than once per arm.
+//!
+//! They look through the [`Traced`] wrapper of errors raised from a `traced`
+//! `catch!`.
+//!
+//! [`Traced`]: crate::Traced
+use alloc::boxed::Box;
+use core::error::Error;
+
+#[cfg(feature = "std")]
+use crate::trace::Traced;
+
+/// Whether the error behind `error` is a `T`.
+#[inline]
+pub fn is<T: Error + 'static>(error: &(dyn Error + 'static)) -> bool {
+    untraced(error).is::<T>()
+}
+
+/// Borrows the error behind `error` if it is a `T`.
+#[inline]
+pub fn downcast_ref<'a, T: Error + 'static>(error: &'a (dyn Error + 'static)) -> Option<&'a T> {
+    untraced(error).downcast_ref()
+}
+
+/// Moves the error of type `T` out of the box.
+#[inline]
+pub fn unbox<T: Error + 'static>(error: Box<dyn Error>) -> T {
+    #[cfg(feature = "std")]
+    let error = untrace(error);
+    match error.downcast() {
+        Ok(error) => *error,
+        Err(_) => checked(),
+    }
+}
+
+/// Borrows the error of type `T` behind `error`.
+#[inline]
+pub fn borrow<'a, T: Error + 'static>(error: &'a (dyn Error + 'static)) -> &'a T {
+    match untraced(error).downcast_ref() {
+        Some(error) => error,
+        None => checked(),
+    }
+}
+
+/// Borrows the error of type `T` behind `error` mutably.
+#[inline]
+pub fn borrow_mut<'a, T: Error + 'static>(error: &'a mut (dyn Error + 'static)) -> &'a mut T {
+    match untraced_mut(error).downcast_mut() {
+        Some(error) => error,
+        None => checked(),
+    }
+}
+
+/// Takes the error that a check of the catch arm found, such as the source
+/// of the type of a `deep` arm, or the downcast of a custom carrier.
+#[inline]
+pub fn found<T>(found: Option<T>) -> T {
+    match found {
+        Some(found) => found,
+        None => checked(),
+    }
+}
+
+/// Like [`found`], for the downcasts that give the error back on failure.
+#[inline]
+pub fn downcast<T, E>(downcast: Result<T, E>) -> T {
+    match downcast {
+        Ok(error) => error,
+        Err(_) => checked(),
+    }
+}
+
+#[cold]
+fn checked() -> ! {
+    unreachable!("the catch arm has already checked the type of the error")
+}
+
+/// Takes the value out of the result of the try block once it is known to
+/// have succeeded.
+#[inline]
+pub fn take_ok<T, E>(result: Result<T, E>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(_) => taken(),
+    }
+}
+
+/// Takes the error out of the result of the try block once it is known to
+/// have failed.
+#[inline]
+pub fn take_err<T, E>(result: Result<T, E>) -> E {
+    match result {
+        Ok(_) => taken(),
+        Err(error) => error,
+    }
+}
+
+#[cold]
+fn taken() -> ! {
+    unreachable!("the result of the try block has already been checked")
+}
+
+/// The end of the handlers of the catch arms, which no error reaches when
+/// the arms handle them all.
+#[cold]
+pub fn handled() -> ! {
+    unreachable!("the catch arms have already handled every error")
+}
+
+/// The end of the match on the pattern of a catch arm, which its guard has
+/// already matched.
+#[cold]
+pub fn matched() -> ! {
+    unreachable!("the catch arm has already matched the error")
+}
+
+/// The error inside `error` if it is `Traced`, or `error` itself.
+#[inline]
+pub fn untraced<'a>(error: &'a (dyn Error + 'static)) -> &'a (dyn Error + 'static) {
+    #[cfg(feature = "std")]
+    if let Some(traced) = error.downcast_ref::<Traced>() {
+        return &*traced.error;
+    } else if let Some(traced) = error.downcast_ref::<Traced<dyn Error + Send + Sync>>() {
+        return &*traced.error;
+    }
+    error
+}
+
+/// Like [`untraced`], but borrows the error mutably.
+#[inline]
+pub fn untraced_mut<'a>(error: &'a mut (dyn Error + 'static)) -> &'a mut (dyn Error + 'static) {
+    #[cfg(feature = "std")]
+    if error.is::<Traced>() {
+        return &mut *error.downcast_mut::<Traced>().unwrap().error;
+    } else if error.is::<Traced<dyn Error + Send + Sync>>() {
+        let traced = error.downcast_mut::<Traced<dyn Error + Send + Sync>>();
+        return &mut *traced.unwrap().error;
+    }
+    error
+}
+
+/// Like [`untraced`], but takes the error out of its box.
+#[cfg(feature = "std")]
+fn untrace(error: Box<dyn Error>) -> Box<dyn Error> {
+    match error.downcast::<Traced>() {
+        Ok(traced) => traced.error,
+        Err(error) => match error.downcast::<Traced<dyn Error + Send + Sync>>() {
+            Ok(traced) => traced.error,
+            Err(error) => error,
+        },
+    }
+}
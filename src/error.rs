@@ -0,0 +1,883 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use core::any::Any;
+use core::error::Error;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use core::panic::Location;
+
+use crate::downcast::untraced;
+
+/// An error that escaped a `catch!` with a `context` clause.
+///
+/// Its message is the context followed by the message of the original error,
+/// which stays reachable through [`Error::source`].
+#[derive(Debug)]
+pub struct ContextError {
+    context: String,
+    source: Box<dyn Error>,
+}
+
+impl ContextError {
+    pub fn new(context: impl fmt::Display, source: Box<dyn Error>) -> Self {
+        ContextError {
+            context: context.to_string(),
+            source,
+        }
+    }
+
+    pub fn context(&self) -> &str {
+        &self.context
+    }
+
+    /// Returns the original error, discarding the context.
+    pub fn into_source(self) -> Box<dyn Error> {
+        self.source
+    }
+}
+
+impl fmt::Display for ContextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.context, self.source)
+    }
+}
+
+impl Error for ContextError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+/// An error that escaped a `catch!` marked `located`.
+///
+/// Its message is the message of the original error followed by the location
+/// of the `catch!`, and the original error stays reachable through
+/// [`Error::source`].
+#[derive(Debug)]
+pub struct Located {
+    location: &'static Location<'static>,
+    source: Box<dyn Error>,
+}
+
+impl Located {
+    pub fn new(location: &'static Location<'static>, source: Box<dyn Error>) -> Self {
+        Located { location, source }
+    }
+
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    /// Returns the original error, discarding the location.
+    pub fn into_source(self) -> Box<dyn Error> {
+        self.source
+    }
+}
+
+impl fmt::Display for Located {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}:{}",
+            self.source,
+            self.location.file(),
+            self.location.line()
+        )
+    }
+}
+
+impl Error for Located {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+/// An error propagated by `rethrow with message` from a catch arm.
+///
+/// Its message is the one given to `rethrow with`, and the caught error is kept
+/// as its [`Error::source`].
+#[derive(Debug)]
+pub struct Rethrown {
+    message: String,
+    source: Box<dyn Error>,
+}
+
+impl Rethrown {
+    pub fn new(message: impl fmt::Display, source: impl Into<Box<dyn Error>>) -> Self {
+        Rethrown {
+            message: message.to_string(),
+            source: source.into(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the caught error, discarding the message.
+    pub fn into_source(self) -> Box<dyn Error> {
+        self.source
+    }
+}
+
+impl fmt::Display for Rethrown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for Rethrown {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+/// An ad hoc error made of a message, as raised by [`throw!`](crate::throw).
+#[derive(Debug)]
+pub struct MessageError {
+    message: String,
+}
+
+impl MessageError {
+    pub fn new(message: impl fmt::Display) -> Self {
+        MessageError {
+            message: message.to_string(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for MessageError {}
+
+/// Raises a [`MessageError`] with a message formatted like `format!`.
+///
+/// Inside a try block it leaves the block with the error, so it reaches the
+/// catch arms like any other error. Elsewhere it returns the error from the
+/// enclosing function, which has to return a `Result` whose error type can be
+/// built from a [`MessageError`], such as `Box<dyn Error>`.
+///
+/// ```rust
+/// # use try_catch::{catch, throw};
+/// let id = 3;
+/// let message: String = catch! {
+///     try {
+///         if id > 2 {
+///             throw!("user {id} has no profile");
+///         }
+///         String::new()
+///     } catch error {
+///         error.to_string()
+///     }
+/// };
+/// assert_eq!(message, "user 3 has no profile");
+/// ```
+#[macro_export]
+macro_rules! throw {
+    ($($format:tt)+) => {
+        return ::core::result::Result::Err(::core::convert::From::from(
+            $crate::MessageError::new($crate::__private::format!($($format)+)),
+        ))
+    };
+}
+
+/// Returns early with an error, like `return Err(error.into())`.
+///
+/// Given a string literal, optionally followed by format arguments, the error is
+/// a [`MessageError`] with the formatted message. Given any other expression,
+/// the value itself is converted with [`From`], so a concrete error stays
+/// catchable by the typed arms of `catch!`. Like [`throw!`](crate::throw), it
+/// leaves the try block when used in one, and otherwise returns from the
+/// enclosing function.
+///
+/// ```rust
+/// # use try_catch::bail;
+/// # use std::{error::Error, io};
+/// fn open(path: &str) -> Result<(), Box<dyn Error>> {
+///     if path.is_empty() {
+///         bail!(io::Error::new(io::ErrorKind::InvalidInput, "empty path"));
+///     }
+///     bail!("cannot open {path}")
+/// }
+///
+/// assert!(open("").unwrap_err().is::<io::Error>());
+/// assert_eq!(open("/home").unwrap_err().to_string(), "cannot open /home");
+/// ```
+#[macro_export]
+macro_rules! bail {
+    ($message:literal $(,)?) => {
+        return ::core::result::Result::Err(::core::convert::From::from(
+            $crate::MessageError::new($crate::__private::format!($message)),
+        ))
+    };
+    ($error:expr $(,)?) => {
+        return ::core::result::Result::Err(::core::convert::From::from($error))
+    };
+    ($format:literal, $($args:tt)+) => {
+        return ::core::result::Result::Err(::core::convert::From::from(
+            $crate::MessageError::new($crate::__private::format!($format, $($args)+)),
+        ))
+    };
+}
+
+/// Returns early with an error unless the condition holds.
+///
+/// The error is given as for [`bail!`](crate::bail).
+///
+/// ```rust
+/// # use try_catch::{catch, ensure};
+/// let age = 12;
+/// let allowed: bool = catch! {
+///     try {
+///         ensure!(age >= 18, "{age} is too young");
+///         true
+///     } catch error {
+///         assert_eq!(error.to_string(), "12 is too young");
+///         false
+///     }
+/// };
+/// assert!(!allowed);
+/// ```
+#[macro_export]
+macro_rules! ensure {
+    ($condition:expr, $($error:tt)+) => {
+        if !$condition {
+            $crate::bail!($($error)+);
+        }
+    };
+}
+
+/// Evaluates a try block, or a default value if it raises an error.
+///
+/// `try_or!({ .. }, default)` is a `catch!` with a single wildcard arm, for
+/// the values that fall back on a default after a few fallible steps. Given
+/// as a closure, the default sees the error, which it can log or inspect.
+///
+/// ```rust
+/// # use try_catch::try_or;
+/// # use std::collections::HashMap;
+/// let env = HashMap::from([("PORT", "80"), ("WORKERS", "many")]);
+///
+/// let port: u16 = try_or!({ env.get("PORT")?.parse()? }, 8080);
+/// assert_eq!(port, 80);
+///
+/// let mut logged = vec![];
+/// let workers: u8 = try_or!({ env.get("WORKERS")?.parse()? }, |error| {
+///     logged.push(error.to_string());
+///     4
+/// });
+/// assert_eq!(workers, 4);
+/// assert_eq!(logged, ["invalid digit found in string"]);
+/// ```
+#[macro_export]
+macro_rules! try_or {
+    ($body:tt, |$error:tt| $default:expr $(,)?) => {
+        $crate::catch! {
+            try $body
+            catch $error => $default,
+        }
+    };
+    ($body:tt, $default:expr $(,)?) => {
+        $crate::catch! {
+            try $body
+            catch _ => $default,
+        }
+    };
+}
+
+/// An error raised by a catch arm while it was handling another error.
+///
+/// Keeps the error that triggered the arm as its [`Error::source`] so the root
+/// cause of a failing handler is not lost. Usually built with
+/// [`WhileHandling::while_handling`].
+#[derive(Debug)]
+pub struct HandlerError {
+    error: Box<dyn Error>,
+    original: Box<dyn Error>,
+}
+
+impl HandlerError {
+    pub fn new(error: impl Into<Box<dyn Error>>, original: impl Into<Box<dyn Error>>) -> Self {
+        HandlerError {
+            error: error.into(),
+            original: original.into(),
+        }
+    }
+
+    /// The error raised by the catch arm.
+    pub fn error(&self) -> &(dyn Error + 'static) {
+        &*self.error
+    }
+
+    /// The error the catch arm was handling.
+    pub fn original(&self) -> &(dyn Error + 'static) {
+        &*self.original
+    }
+
+    pub fn into_parts(self) -> (Box<dyn Error>, Box<dyn Error>) {
+        (self.error, self.original)
+    }
+}
+
+impl fmt::Display for HandlerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (while handling: {})", self.error, self.original)
+    }
+}
+
+impl Error for HandlerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.original)
+    }
+}
+
+/// A panic caught by a `catch panic payload { .. }` arm.
+///
+/// Its message is the one given to `panic!`, when the payload is a string.
+pub struct Panicked {
+    payload: Box<dyn Any + Send>,
+}
+
+impl Panicked {
+    pub fn new(payload: Box<dyn Any + Send>) -> Self {
+        Panicked { payload }
+    }
+
+    /// The panic message, or `None` if the payload is not a string.
+    pub fn message(&self) -> Option<&str> {
+        match self.payload.downcast_ref::<&'static str>() {
+            Some(message) => Some(message),
+            None => self.payload.downcast_ref::<String>().map(String::as_str),
+        }
+    }
+
+    /// Returns the payload, to resume unwinding with it for instance.
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+}
+
+impl fmt::Debug for Panicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Panicked")
+            .field("message", &self.message())
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for Panicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.message() {
+            Some(message) => write!(f, "panicked: {}", message),
+            None => write!(f, "panicked"),
+        }
+    }
+}
+
+impl Error for Panicked {}
+
+/// Attaches the error a catch arm is handling to the errors raised inside it.
+/// ```rust
+/// # use try_catch::{catch, HandlerError, WhileHandling};
+/// # use std::error::Error;
+/// let result: Result<i32, HandlerError> = catch! {
+///     try {
+///         Ok("ten".parse::<i32>()?)
+///     } catch error {
+///         "eleven".parse::<i32>().while_handling(error)
+///     }
+/// };
+/// let error = result.unwrap_err();
+/// assert_eq!(error.original().to_string(), "invalid digit found in string");
+/// assert!(error.source().is_some());
+/// ```
+pub trait WhileHandling<T> {
+    fn while_handling(self, original: impl Into<Box<dyn Error>>) -> Result<T, HandlerError>;
+}
+
+impl<T, E> WhileHandling<T> for Result<T, E>
+where
+    E: Into<Box<dyn Error>>,
+{
+    fn while_handling(self, original: impl Into<Box<dyn Error>>) -> Result<T, HandlerError> {
+        self.map_err(|error| HandlerError::new(error, original))
+    }
+}
+
+/// Returns the first error of type `T` in the chain of `error` and its sources,
+/// starting with `error` itself, for `catch deep` arms. Errors shared in an
+/// `Arc<dyn Error>` are looked through.
+///
+/// The outermost error of the type wins:
+/// ```rust
+/// # use try_catch::catch;
+/// # use std::{error::Error, fmt};
+/// #[derive(Debug)]
+/// struct Layer(u8, Option<Box<Layer>>);
+/// impl fmt::Display for Layer {
+///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         write!(f, "layer {}", self.0)
+///     }
+/// }
+/// impl Error for Layer {
+///     fn source(&self) -> Option<&(dyn Error + 'static)> {
+///         self.1.as_deref().map(|layer| layer as _)
+///     }
+/// }
+///
+/// let depth = catch! {
+///     try {
+///         throw Layer(1, Some(Box::new(Layer(2, None))));
+///     }
+///     catch deep layer: Layer => layer.0,
+/// };
+/// assert_eq!(depth.unwrap(), 1);
+/// ```
+pub fn find_source<'a, T: Error + 'static>(error: &'a (dyn Error + 'static)) -> Option<&'a T> {
+    let mut error = Some(error);
+    while let Some(current) = error {
+        if let Some(found) = current.downcast_ref::<T>() {
+            return Some(found);
+        }
+        // A shared error delegates `source()` to the error it holds, which
+        // would skip that error.
+        error = if let Some(shared) = current.downcast_ref::<Arc<dyn Error + Send + Sync>>() {
+            Some(&**shared)
+        } else if let Some(shared) = current.downcast_ref::<Arc<dyn Error>>() {
+            Some(&**shared)
+        } else {
+            current.source()
+        };
+    }
+    None
+}
+
+/// Displays a `caused by: ..` line for each of the sources of an error, as
+/// reports of unhandled errors end with.
+pub(crate) struct Sources<'a>(pub &'a (dyn Error + 'static));
+
+impl fmt::Display for Sources<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `Traced` wrappers display as the error they wrap, which is also
+        // their source, so they are skipped to not report it twice.
+        let mut source = untraced(self.0).source().map(untraced);
+        while let Some(error) = source {
+            write!(f, "\ncaused by: {}", error)?;
+            source = error.source().map(untraced);
+        }
+        Ok(())
+    }
+}
+
+/// Panics for an error that escaped a `catch!` in `expect` mode, with `message`
+/// followed by the error and the chain of its sources.
+#[cold]
+#[track_caller]
+pub fn unhandled(message: &str, error: &(dyn Error + 'static)) -> ! {
+    let error = untraced(error);
+    panic!("{}: {}{}", message, error, Sources(error))
+}
+
+/// The types that typed catch arms can downcast the boxed error to.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be caught, since it does not implement `Error`",
+    label = "the type in this catch arm must implement `std::error::Error` and be `'static`"
+)]
+pub trait Catchable: Error + 'static {}
+
+impl<T: Error + 'static> Catchable for T {}
+
+/// Checks the type of a catch arm, so that a type that cannot be caught is
+/// reported at the arm rather than at the downcast the arm expands to.
+#[inline(always)]
+pub fn assert_catchable<T: Catchable>() {}
+
+/// The error raised by `?` on a `None` in a try block, which `catch none` arms
+/// catch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct NoneError;
+
+impl fmt::Display for NoneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("`?` was applied to a `None` value")
+    }
+}
+
+impl Error for NoneError {}
+
+/// The values that `?` can be applied to in a try block.
+#[diagnostic::on_unimplemented(
+    message = "the `?` operator can only be applied to a `Result` or an `Option` in a try block",
+    label = "the `?` operator cannot be applied to type `{Self}`"
+)]
+pub trait Fallible {
+    type Value;
+    type Error;
+
+    fn into_result(self) -> Result<Self::Value, Self::Error>;
+}
+
+impl<T, E> Fallible for Result<T, E> {
+    type Value = T;
+    type Error = E;
+
+    #[inline(always)]
+    fn into_result(self) -> Self {
+        self
+    }
+}
+
+impl<T> Fallible for Option<T> {
+    type Value = T;
+    type Error = NoneError;
+
+    #[inline(always)]
+    fn into_result(self) -> Result<T, NoneError> {
+        self.ok_or(NoneError)
+    }
+}
+
+/// The operand of a `?` in a try block as a `Result`, with `None` as a
+/// [`NoneError`].
+#[inline(always)]
+pub fn fallible<R: Fallible>(value: R) -> Result<R::Value, R::Error> {
+    value.into_result()
+}
+
+/// Implemented by the errors listed in `#[throws(..)]`, given as the list `S`
+/// of nested pairs ending in `()`. `I` is the position of the error in the
+/// list, left for the compiler to infer.
+#[diagnostic::on_unimplemented(
+    message = "`?` couldn't raise `{Self}`, which is not one of the errors listed by `#[throws]`",
+    label = "`{Self}` is not listed in `#[throws(..)]`"
+)]
+pub trait Declared<S, I> {}
+
+/// The position of the first error of a [`Declared`] list.
+pub struct Here;
+
+/// The position `I` in the rest of a [`Declared`] list.
+pub struct There<I>(I);
+
+impl<E, Rest> Declared<(E, Rest), Here> for E {}
+
+impl<E, First, Rest, I> Declared<(First, Rest), There<I>> for E where E: Declared<Rest, I> {}
+
+/// Checks that the error of a `?` in a `#[throws(..)]` function is listed in `S`.
+#[inline(always)]
+pub fn declared<S, I, T, E: Declared<S, I>>(result: Result<T, E>) -> Result<T, E> {
+    result
+}
+
+/// The error carried to the arms of a `catch!` in `propagate` mode, along with
+/// the conversion into `E`, the error type of the enclosing function, that `?`
+/// would have applied to it. The arms downcast it like a box, and the errors
+/// that no arm handles are returned from the function as they were raised.
+///
+/// It does not implement `Error`, so that any error can be converted into it.
+pub struct Propagating<E> {
+    error: Box<dyn Error>,
+    convert: fn(Box<dyn Error>) -> E,
+}
+
+impl<E> Propagating<E> {
+    fn new<T, M>(error: T) -> Self
+    where
+        T: Propagate<E, M>,
+    {
+        Propagating {
+            error: error.boxed(),
+            convert: T::convert,
+        }
+    }
+
+    #[inline]
+    pub fn is<T: Error + 'static>(&self) -> bool {
+        crate::downcast::is::<T>(&*self.error)
+    }
+
+    #[inline]
+    pub fn downcast_ref<T: Error + 'static>(&self) -> Option<&T> {
+        crate::downcast::downcast_ref(&*self.error)
+    }
+
+    /// Moves the error out if it is a `T`.
+    pub fn downcast<T: Error + 'static>(self) -> Result<T, Self> {
+        if self.is::<T>() {
+            return Ok(crate::downcast::unbox(self.error));
+        }
+        Err(self)
+    }
+
+    /// The error, converted into the error type of the enclosing function.
+    #[inline]
+    pub fn into_error(self) -> E {
+        (self.convert)(self.error)
+    }
+
+    /// The error, boxed as it is given to the arms.
+    #[inline]
+    pub fn into_box(self) -> Box<dyn Error> {
+        self.error
+    }
+}
+
+impl<E> Deref for Propagating<E> {
+    type Target = dyn Error;
+
+    fn deref(&self) -> &(dyn Error + 'static) {
+        &*self.error
+    }
+}
+
+impl<E> DerefMut for Propagating<E> {
+    fn deref_mut(&mut self) -> &mut (dyn Error + 'static) {
+        &mut *self.error
+    }
+}
+
+impl<E> fmt::Debug for Propagating<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<E> fmt::Display for Propagating<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<T, E> From<T> for Propagating<E>
+where
+    T: Error + 'static,
+    E: From<T>,
+{
+    #[inline]
+    fn from(error: T) -> Self {
+        Propagating::new::<T, Concrete>(error)
+    }
+}
+
+/// The errors that `?` can raise in a try block in `propagate` mode, where `E`
+/// is the error type of the enclosing function. `M` tells the kinds of errors
+/// apart, and is left for the compiler to infer.
+#[diagnostic::on_unimplemented(
+    message = "`?` couldn't convert the error to `{E}`, the error type of the function",
+    label = "`{E}` cannot be built from `{Self}`, which `propagate` would return"
+)]
+pub trait Propagate<E, M>: Sized {
+    /// The error as it is given to the arms.
+    fn boxed(self) -> Box<dyn Error>;
+
+    /// Converts the error, boxed by [`boxed`](Propagate::boxed), into `E`.
+    fn convert(error: Box<dyn Error>) -> E;
+}
+
+/// The [`Propagate`] marker of the types implementing `Error`.
+pub struct Concrete;
+
+/// The [`Propagate`] marker of boxed errors.
+pub struct Opaque;
+
+impl<T, E> Propagate<E, Concrete> for T
+where
+    T: Error + 'static,
+    E: From<T>,
+{
+    #[inline]
+    fn boxed(self) -> Box<dyn Error> {
+        Box::new(self)
+    }
+
+    fn convert(error: Box<dyn Error>) -> E {
+        E::from(crate::downcast::unbox(error))
+    }
+}
+
+impl<E: From<Box<dyn Error>>> Propagate<E, Opaque> for Box<dyn Error> {
+    #[inline]
+    fn boxed(self) -> Box<dyn Error> {
+        self
+    }
+
+    #[inline]
+    fn convert(error: Box<dyn Error>) -> E {
+        E::from(error)
+    }
+}
+
+impl<E: From<Box<dyn Error>>> Propagate<E, Opaque> for Box<dyn Error + Send + Sync> {
+    #[inline]
+    fn boxed(self) -> Box<dyn Error> {
+        self
+    }
+
+    #[inline]
+    fn convert(error: Box<dyn Error>) -> E {
+        E::from(error)
+    }
+}
+
+/// Converts the error of a `?` in a try block in `propagate` mode.
+#[inline(always)]
+pub fn propagate<T, R, E, M>(result: Result<T, R>) -> Result<T, Propagating<E>>
+where
+    R: Propagate<E, M>,
+{
+    result.map_err(Propagating::new)
+}
+
+/// Converts the error of a `?` in a try block whose errors are carried as `C`,
+/// a box, so that an error that cannot be boxed is reported at the `?` rather
+/// than where it is boxed, and so that strings are boxed as [`DisplayError`]s.
+#[inline(always)]
+pub fn raise<C, T, E: Raise<C, M>, M>(result: Result<T, E>) -> Result<T, Raised<C>> {
+    result.map_err(|error| Raised(error.raise()))
+}
+
+/// An error raised with `?` in a try block, already in its box.
+pub struct Raised<C>(C);
+
+/// The errors that `?` can raise in a try block whose errors are carried as `C`.
+/// `M` is [`Converted`] for errors that convert into `C`, or [`Unsized`] for
+/// boxes of errors that `C` only holds through an unsizing coercion.
+#[diagnostic::on_unimplemented(
+    message = "`?` couldn't convert the error to `{C}`",
+    label = "`{Self}` cannot be boxed as `{C}`, it must implement `Error` or be a box of one",
+    note = "a value that only implements `Display` can be raised as `try_catch::DisplayError(value)`"
+)]
+pub trait Raise<C, M> {
+    fn raise(self) -> C;
+}
+
+/// Marks the errors that convert into their carrier.
+pub struct Converted;
+
+/// Marks the boxes of errors that coerce into their carrier, such as a
+/// `Box<dyn Error + Send + Sync>` raised where errors are a `Box<dyn Error>`.
+pub struct Unsized;
+
+impl<E, C> Raise<C, Converted> for E
+where
+    E: Into<C> + 'static,
+    C: From<DisplayError<String>> + From<DisplayError<&'static str>>,
+{
+    #[inline]
+    fn raise(self) -> C {
+        // Strings would otherwise be boxed as an error type of the standard
+        // library that cannot be downcast to.
+        let mut error = Some(self);
+        let any: &mut dyn Any = &mut error;
+        if let Some(message) = any.downcast_mut::<Option<String>>() {
+            return DisplayError(message.take().unwrap()).into();
+        }
+        if let Some(message) = any.downcast_mut::<Option<&'static str>>() {
+            return DisplayError(message.take().unwrap()).into();
+        }
+        error.unwrap().into()
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl Raise<Box<dyn Error>, Unsized> for Box<dyn Error + Send + Sync> {
+    #[inline]
+    fn raise(self) -> Box<dyn Error> {
+        self
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl Raise<Box<dyn Error + Send>, Unsized> for Box<dyn Error + Send + Sync> {
+    #[inline]
+    fn raise(self) -> Box<dyn Error + Send> {
+        self
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl Raise<Box<dyn Error>, Unsized> for Box<dyn Error + Send> {
+    #[inline]
+    fn raise(self) -> Box<dyn Error> {
+        self
+    }
+}
+
+macro_rules! impl_from_raised {
+    ($($carrier:ty),*) => {$(
+        impl From<Raised<$carrier>> for $carrier {
+            #[inline]
+            fn from(Raised(error): Raised<$carrier>) -> Self {
+                error
+            }
+        }
+    )*};
+}
+
+impl_from_raised!(
+    Box<dyn Error>,
+    Box<dyn Error + Send>,
+    Box<dyn Error + Send + Sync>
+);
+
+/// An error made of a value that only implements [`Display`](fmt::Display).
+///
+/// The strings raised with `?` or `throw` in a try block are boxed as a
+/// `DisplayError<String>` or a `DisplayError<&'static str>`, which arms
+/// catching `String` or `&'static str` unwrap. Other values can be raised as
+/// `DisplayError(value)`, and caught with a `DisplayError(value)` pattern.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct DisplayError<T>(pub T);
+
+impl<T> DisplayError<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for DisplayError<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for DisplayError<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Display> fmt::Debug for DisplayError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DisplayError")
+            .field(&format_args!("{}", self.0))
+            .finish()
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for DisplayError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Display> Error for DisplayError<T> {}
+
+/// The standard library only boxes errors as `dyn Error` or as
+/// `dyn Error + Send + Sync`.
+impl<T: fmt::Display + Send + 'static> From<DisplayError<T>> for Box<dyn Error + Send> {
+    fn from(error: DisplayError<T>) -> Self {
+        Box::new(error)
+    }
+}
@@ -27,10 +27,12 @@
 //! Note, if no wildcard is present then the compiler will warn about unused results.
 //! It can also be used as an expression:
 //! ```rust
-//! // We can guarantee that all errors are catched 
+//! # use try_catch::catch;
+//! # use std::io;
+//! // We can guarantee that all errors are catched
 //! // so the type of this expression is `i32`.
-//! // It can be guaranteed because the final catch 
-//! // does not specify an Error type. 
+//! // It can be guaranteed because the final catch
+//! // does not specify an Error type.
 //! let number: i32 = catch! {
 //!     try {
 //!         let number: i32 = "10".parse()?;
@@ -39,9 +41,9 @@
 //!         0
 //!     }
 //! };
-//! // we can't know for sure if all possible errors are 
-//! // handled so the type of this expression 
-//! // is still Result. 
+//! // we can't know for sure if all possible errors are
+//! // handled so the type of this expression
+//! // is still Result.
 //! let result: Result<i32, _> = catch! {
 //!     try {
 //!         let number: i32 = "invalid number".parse()?;
@@ -51,158 +53,3125 @@
 //!     }
 //! };
 //! ```
+//! Only an untyped arm without a guard handles every error. With a guard, the
+//! expression is still a `Result`:
+//! ```rust
+//! # use try_catch::catch;
+//! let number: Result<i32, _> = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     } catch error if error.to_string().contains("digit") {
+//!         0
+//!     }
+//! };
+//! assert_eq!(number.unwrap(), 0);
+//! ```
+//! Arms are tried in order, so the wildcard arm has to be the last one. An arm
+//! after it could never run and is rejected, as is a second wildcard arm:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     } catch error {
+//!         0
+//!     // error: this arm never runs, since the wildcard arm before it catches every error
+//!     } catch error: ParseIntError {
+//!         1
+//!     }
+//! };
+//! ```
+//! ```compile_fail
+//! # use try_catch::catch;
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch error => 0,
+//!     // error: this arm never runs, since the wildcard arm before it catches every error
+//!     catch _ => 1,
+//! };
+//! ```
+//! The same goes for an arm catching a type that an earlier arm always
+//! catches. When the two arms name the type through different paths, as with
+//! `io::Error` and `std::io::Error`, they may still be different types, and
+//! the macro warns instead.
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::io;
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch error: io::Error => 0,
+//!     // error: this arm never runs for `io::Error`, which an earlier arm already catches
+//!     catch error: io::Error if error.kind() == io::ErrorKind::NotFound => 1,
+//!     catch error => 2,
+//! };
+//! ```
+//! ```compile_fail
+//! #![deny(deprecated)]
+//! # use try_catch::catch;
+//! # use std::io;
+//! # fn main() {
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch error: io::Error => 0,
+//!     // warning: this arm never runs if `std::io::Error` is the same type as `io::Error`
+//!     catch error: std::io::Error => 1,
+//!     catch error => 2,
+//! };
+//! # }
+//! ```
+//!
+//! The types of the arms must implement [`std::error::Error`] and be
+//! `'static`, which is what downcasting the boxed error needs. Other types are
+//! reported at the arm:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! #[derive(Debug)]
+//! struct Status(u16);
+//!
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     // error: `Status` cannot be caught, since it does not implement `Error`
+//!     catch status: Status => status.0.into(),
+//!     catch error => 0,
+//! };
+//! ```
+//! Errors reach the arms boxed, so a `Box<dyn Error>` is never caught as such,
+//! and `anyhow::Error` does not implement `Error`. Both are rejected with a
+//! hint: catch the types they hold, or carry errors as `anyhow::Error` with
+//! `error = anyhow::Error`, described in [Error carriers](#error-carriers).
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::error::Error;
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     // error: errors reach the arms already boxed, so a `Box` cannot be caught
+//!     catch error: Box<dyn Error> => 1,
+//!     catch error => 0,
+//! };
+//! ```
+//! ```compile_fail
+//! # use try_catch::catch;
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     // error: `anyhow::Error` does not implement `std::error::Error`, so it cannot be caught
+//!     catch error: anyhow::Error => 1,
+//!     catch error => 0,
+//! };
+//! ```
+//!
+//! The type of an arm may also be a type parameter, which is caught whenever
+//! the error is an instance of it:
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{error::Error, io, num::ParseIntError};
+//! fn caught<E: Error + 'static>(fail: impl FnOnce() -> Result<(), Box<dyn Error>>) -> bool {
+//!     catch! {
+//!         try {
+//!             fail()?;
+//!             false
+//!         }
+//!         catch _: E => true,
+//!         catch _ => false,
+//!     }
+//! }
+//!
+//! let parse = || Err("ten".parse::<i32>().unwrap_err().into());
+//! let io = || Err(io::Error::from(io::ErrorKind::NotFound).into());
+//! assert!(caught::<ParseIntError>(parse));
+//! assert!(caught::<io::Error>(io));
+//! assert!(!caught::<io::Error>(parse));
+//! assert!(!caught::<ParseIntError>(io));
+//! ```
+//! The parameter needs both bounds, and the missing one is reported at the arm:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::fmt::Debug;
+//! fn caught<E: Debug + 'static>() -> bool {
+//!     catch! {
+//!         try {
+//!             "ten".parse::<i32>()?;
+//!             false
+//!         }
+//!         // error: `E` cannot be caught, since it does not implement `Error`
+//!         catch _: E => true,
+//!         catch _ => false,
+//!     }
+//! }
+//! ```
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::error::Error;
+//! fn caught<E: Error>() -> bool {
+//!     catch! {
+//!         try {
+//!             "ten".parse::<i32>()?;
+//!             false
+//!         }
+//!         // error: the parameter type `E` may not live long enough
+//!         catch _: E => true,
+//!         catch _ => false,
+//!     }
+//! }
+//! ```
+//!
+//! However many arms there are, each error goes to the first arm that matches
+//! it, whether by type, by guard or through its sources:
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{fmt, io, num, str, string, char, cell, array, sync, convert::TryFrom};
+//! #[derive(Debug)]
+//! struct Wrapped(io::Error);
+//! # impl fmt::Display for Wrapped {
+//! #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "wrapped") }
+//! # }
+//! # impl std::error::Error for Wrapped {
+//! #     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> { Some(&self.0) }
+//! # }
+//!
+//! fn arm(input: &str) -> &'static str {
+//!     catch! {
+//!         try {
+//!             match input {
+//!                 "int" => drop("x".parse::<i32>()?),
+//!                 "float" => drop("x".parse::<f64>()?),
+//!                 "bool" => drop("x".parse::<bool>()?),
+//!                 "char" => drop("xy".parse::<char>()?),
+//!                 "utf8" => drop(str::from_utf8(&[0xff])?),
+//!                 "string" => drop(String::from_utf8(vec![0xff])?),
+//!                 "narrow" => drop(u8::try_from(300_i32)?),
+//!                 "borrow" => {
+//!                     let cell = cell::RefCell::new(0);
+//!                     let _borrowed = cell.borrow_mut();
+//!                     drop(cell.try_borrow()?);
+//!                 }
+//!                 "array" => drop(<[u8; 2]>::try_from(&[1_u8][..])?),
+//!                 "fmt" => { throw fmt::Error; }
+//!                 "wrapped" => { throw Wrapped(io::ErrorKind::NotFound.into()); }
+//!                 "denied" => { throw io::Error::from(io::ErrorKind::PermissionDenied); }
+//!                 _ => { throw io::Error::from(io::ErrorKind::NotFound); }
+//!             }
+//!             "none"
+//!         }
+//!         catch _: num::ParseIntError if input != "int" => "guarded int",
+//!         catch _: num::ParseIntError => "int",
+//!         catch _: num::ParseFloatError => "float",
+//!         catch _: str::ParseBoolError => "bool",
+//!         catch _: char::ParseCharError => "char",
+//!         catch _: str::Utf8Error => "utf8",
+//!         catch _: string::FromUtf8Error => "string",
+//!         catch _: num::TryFromIntError => "narrow",
+//!         catch _: cell::BorrowError => "borrow",
+//!         catch _: array::TryFromSliceError => "array",
+//!         catch _: io::Error(PermissionDenied) => "denied",
+//!         catch deep _: io::Error => "io",
+//!         catch _: fmt::Error | sync::mpsc::RecvError => "fmt",
+//!         catch _: Wrapped => "wrapped",
+//!         catch _ => "other",
+//!     }
+//! }
+//!
+//! for input in ["int", "float", "bool", "char", "utf8", "string", "narrow", "borrow", "array", "fmt"] {
+//!     assert_eq!(arm(input), input);
+//! }
+//! assert_eq!(arm("denied"), "denied");
+//! assert_eq!(arm("missing"), "io");
+//! // The `deep` arm comes first, and finds the `io::Error` behind `Wrapped`.
+//! assert_eq!(arm("wrapped"), "io");
+//! ```
+//!
+//! Inside the try block, `throw error;` raises an error of your own, which the
+//! catch arms see like any other. It works at any depth within the try block,
+//! including in loops, but not inside closures defined in it. For ad hoc
+//! errors that only need a message, [`throw!`] formats one like `format!`.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{fmt, error::Error};
+//! #[derive(Debug)]
+//! struct Negative(i32);
+//! # impl fmt::Display for Negative {
+//! #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//! #         write!(f, "{} is negative", self.0)
+//! #     }
+//! # }
+//! # impl Error for Negative {}
+//!
+//! let total: i32 = catch! {
+//!     try {
+//!         let mut total = 0;
+//!         for number in ["1", "-2", "3"] {
+//!             let number: i32 = number.parse()?;
+//!             if number < 0 {
+//!                 throw Negative(number);
+//!             }
+//!             total += number;
+//!         }
+//!         total
+//!     } catch error: Negative {
+//!         error.0
+//!     } catch error {
+//!         0
+//!     }
+//! };
+//! assert_eq!(total, -2);
+//! ```
+//!
+//! Outer attributes written before `try` or right before the try block apply to
+//! the try block only, which is handy to scope lints to the fallible section:
+//! ```rust
+//! # #![deny(clippy::unwrap_used)]
+//! # use try_catch::catch;
+//! let number: i32 = catch! {
+//!     try #[allow(clippy::unwrap_used)] {
+//!         let digits = "ten".parse::<i32>()?;
+//!         Some(digits).unwrap()
+//!     } catch error {
+//!         0
+//!     }
+//! };
+//! assert_eq!(number, 0);
+//! ```
+//!
+//! A try block made of a single expression can be written without braces,
+//! ending the expression with a `;`:
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! let number = catch! {
+//!     try "ten".parse::<i32>()?;
+//!     catch error: ParseIntError => 0,
+//!     catch error => 1,
+//! };
+//! assert_eq!(number, 0);
+//! ```
+//!
+//! When the value of the try block cannot be inferred, its type can be written
+//! after an arrow, as in `try -> Vec<u32> { .. }`. A block producing something
+//! else is then reported at the block itself.
+//! ```rust
+//! # use try_catch::catch;
+//! let ports = catch! {
+//!     try -> Vec<u16> {
+//!         let mut ports = Vec::new();
+//!         for port in "80,443".split(',') {
+//!             ports.push(port.parse()?);
+//!         }
+//!         ports
+//!     } catch error {
+//!         vec![]
+//!     }
+//! };
+//! assert_eq!(ports, [80, 443]);
+//! ```
+//!
+//! Since synchronous try blocks run in place, their value is inferred like that
+//! of an ordinary block, from how it is used after the macro. This includes
+//! iterator adapters, closures and generic types whose parameters are only
+//! known later.
+//! ```rust
+//! # use try_catch::catch;
+//! struct Pair<A, B> {
+//!     first: A,
+//!     rest: Vec<B>,
+//! }
+//!
+//! let evens = catch! {
+//!     try {
+//!         let limit: u32 = "10".parse()?;
+//!         (0..limit).filter(|n| n % 2 == 0)
+//!     } catch error {
+//!         panic!("bad limit: {}", error)
+//!     }
+//! };
+//! assert_eq!(evens.count(), 5);
+//!
+//! let add = catch! {
+//!     try {
+//!         let step: u32 = "3".parse()?;
+//!         move |n| n + step
+//!     } catch error {
+//!         panic!("bad step: {}", error)
+//!     }
+//! };
+//! assert_eq!(add(1), 4);
+//!
+//! let mut pair = catch! {
+//!     try {
+//!         let first = "3".parse()?;
+//!         Pair { first, rest: Vec::new() }
+//!     } catch error {
+//!         panic!("bad pair: {}", error)
+//!     }
+//! };
+//! pair.rest.push("four");
+//! let first: u8 = pair.first;
+//! assert_eq!((first, pair.rest), (3, vec!["four"]));
+//! ```
+//!
+//! A try block without any `?` compiles as well, whether it runs in place, in a
+//! closure or as a future. Its errors are still carried as a `Box<dyn Error>`,
+//! so nothing is left for the compiler to guess.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::io;
+//! fn compute<T: Default>() -> T {
+//!     T::default()
+//! }
+//! fn fallback<T: From<u8>>() -> T {
+//!     T::from(1)
+//! }
+//! fn take(number: i32) -> i32 {
+//!     number
+//! }
+//!
+//! let inline = catch! {
+//!     try {
+//!         compute()
+//!     } catch error {
+//!         fallback()
+//!     }
+//! };
+//! let closure = catch! {
+//!     move try {
+//!         compute()
+//!     } catch error: io::Error {
+//!         fallback()
+//!     }
+//! };
+//! let retried = catch! {
+//!     try retry 2 {
+//!         compute()
+//!     } catch error {
+//!         fallback()
+//!     }
+//! };
+//! assert_eq!(take(inline) + take(closure.unwrap()) + take(retried), 0);
+//!
+//! let future = async {
+//!     let awaited = catch! {
+//!         try {
+//!             async {}.await;
+//!             compute()
+//!         } catch error {
+//!             fallback()
+//!         }
+//!     };
+//!     take(awaited)
+//! };
+//! # let _ = future;
+//! ```
+//!
+//! A try block ending in a call that returns a `Result` needs a trailing `?` for
+//! its error to reach the arms; without it, the `Err` would be the value of
+//! the block. Writing `flatten` before `try` adds that `?`, so the value of the
+//! block must be a `Result`, and its error is caught like any other.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! fn parse(text: &str) -> Result<i32, ParseIntError> {
+//!     text.parse()
+//! }
+//!
+//! let number = catch! {
+//!     flatten try {
+//!         parse("ten")
+//!     }
+//!     catch error: ParseIntError => 0,
+//! };
+//! assert_eq!(number.unwrap(), 0);
+//! ```
+//!
+//! Typed arms bind the error by value, with the type of the arm, so it can be
+//! moved out of the arm like any other value:
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{fs, io};
+//! let error: Result<io::Error, _> = catch! {
+//!     try {
+//!         fs::read_to_string("/definitely/not/here")?;
+//!         io::Error::new(io::ErrorKind::Other, "unreachable")
+//!     } catch error: io::Error {
+//!         error
+//!     }
+//! };
+//! assert_eq!(error.unwrap().kind(), io::ErrorKind::NotFound);
+//! ```
+//!
+//! The value of every arm must have the type of the try block. An arm that
+//! does not is reported at its body, next to the value of the try block:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::{io, num::ParseIntError};
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     } catch error: ParseIntError {
+//!         // error: expected `i32`, found `String`
+//!         error.to_string()
+//!     } catch error {
+//!         0
+//!     }
+//! };
+//! ```
+//!
+//! Catch arms made of a single expression can use `=>` instead of a block,
+//! like match arms, with an optional trailing comma:
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{io, num::ParseIntError};
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch error: io::Error => 1,
+//!     catch error: ParseIntError => 2,
+//!     catch error {
+//!         3
+//!     }
+//! };
+//! assert_eq!(number, 2);
+//! ```
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! // error: this catch arm has no body, expected a block or `=> value` after it
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch error: ParseIntError
+//!     catch error => 3,
+//! };
+//! ```
+//! A syntax error in an arm is reported where it is, rather than dropping the
+//! arm and the ones after it:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::{io, num::ParseIntError};
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch error: ParseIntError => 1,
+//!     catch error: io::Error => 2,
+//!     // error: expected expression
+//!     catch error: io::Error if error.kind() == => 3,
+//!     catch error => 4,
+//! };
+//! ```
+//! So are tokens left after the last arm, which would otherwise hide a
+//! misspelled arm or a second try block:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch error: ParseIntError => 1,
+//!     // error: unexpected tokens after the catch arms, expected another `catch e: Type { .. }` arm or `finally { .. }`
+//!     catch error => 2,;
+//! };
+//! ```
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch error: ParseIntError => 1,
+//!     // error: expected a catch arm, as in `catch e: Type { .. }`
+//!     cacth error => 2,
+//! };
+//! ```
+//! ```compile_fail
+//! # use try_catch::catch;
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     // error: `catch!` takes a single try block, expected a catch arm, as in `catch e: Type { .. }`
+//!     try {
+//!         "eleven".parse::<i32>()?
+//!     }
+//!     catch error => 2,
+//! };
+//! ```
+//!
+//! The arrow can also be followed by a handler without binding the error, in
+//! which case the handler is called with the downcast error. Any callable
+//! works, whether a function or a closure stored in a variable. An arm made of
+//! a single identifier followed by `=>`, such as `catch error => 0`, is read as
+//! a binding when the identifier starts in lowercase and as a type otherwise.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! fn handle_parse(error: ParseIntError) -> i32 {
+//!     -1
+//! }
+//!
+//! let number: Result<i32, _> = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch ParseIntError => handle_parse,
+//!     catch std::io::Error => |error| 0,
+//! };
+//! assert_eq!(number.unwrap(), -1);
+//! ```
+//!
+//! Several types can be caught by the same arm by separating them with `|`.
+//! The error is then bound as a `Box<dyn Error>`.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{fmt, io, num::ParseIntError};
+//! let message: String = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?.to_string()
+//!     }
+//!     catch error: io::Error | ParseIntError | fmt::Error => error.to_string(),
+//!     catch error => "unknown".into(),
+//! };
+//! assert_eq!(message, "invalid digit found in string");
+//! ```
+//! Catching the same type twice in one arm is a compile error:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     } catch error: ParseIntError | ParseIntError {
+//!         0
+//!     }
+//! };
+//! ```
+//!
+//! An arm catching [`std::io::Error`] can list the kinds it handles in
+//! parentheses, optionally prefixed by `kind =`. Errors of any other kind fall
+//! through to the next arms. Kinds written as a single identifier refer to
+//! [`std::io::ErrorKind`].
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{fs, io};
+//! let contents: String = catch! {
+//!     try {
+//!         fs::read_to_string("/definitely/not/here")?
+//!     }
+//!     catch error: io::Error(kind = PermissionDenied) => "denied".into(),
+//!     catch error: io::Error(NotFound | InvalidInput) => "missing".into(),
+//!     catch error => "other".into(),
+//! };
+//! assert_eq!(contents, "missing");
+//! ```
+//!
+//! Arms with a binding can be guarded with `if`, like match arms. The guard sees
+//! a reference to the downcast error, and when it is false the error falls
+//! through to the next arms, or is propagated if none of them match.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{fs, io};
+//! let fallback = "default";
+//! let config: Result<String, _> = catch! {
+//!     try {
+//!         fs::read_to_string("/definitely/not/here")?
+//!     }
+//!     catch error: io::Error if error.kind() == io::ErrorKind::PermissionDenied => "denied".into(),
+//!     catch error: io::Error if error.kind() == io::ErrorKind::NotFound && !fallback.is_empty() => {
+//!         fallback.into()
+//!     }
+//! };
+//! assert_eq!(config.unwrap(), "default");
+//! ```
+//!
+//! Instead of a name, the error can be destructured with a pattern, as in
+//! `catch HttpError { status, .. }: HttpError`. When the pattern is a struct
+//! pattern the type can be left out. Like guards, patterns that do not match
+//! let the error fall through to the next arms.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::fmt;
+//! #[derive(Debug)]
+//! struct HttpError {
+//!     status: u16,
+//!     url: String,
+//! }
+//! # impl fmt::Display for HttpError {
+//! #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//! #         write!(f, "{} returned {}", self.url, self.status)
+//! #     }
+//! # }
+//! # impl std::error::Error for HttpError {}
+//!
+//! let message: String = catch! {
+//!     try {
+//!         Err(HttpError { status: 404, url: "/home".into() })?
+//!     }
+//!     catch HttpError { status: 500, .. } => "server error".into(),
+//!     catch HttpError { status, url } => format!("{url}: {status}"),
+//!     catch error => error.to_string(),
+//! };
+//! assert_eq!(message, "/home: 404");
+//! ```
+//!
+//! When the error itself is not needed, it can be bound to `_`:
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{io, num::ParseIntError};
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch _: io::Error => 1,
+//!     catch _: ParseIntError => 2,
+//!     catch _ => 3,
+//! };
+//! assert_eq!(number, 2);
+//! ```
+//! A named binding that the arm never uses is reported by rustc's
+//! `unused_variables` lint at the binding, like any other unused variable, so
+//! that errors are not swallowed by accident. This holds for arms with a guard,
+//! where a binding that only the guard uses counts as used, arms catching by
+//! reference and arms of async try blocks as well.
+//! ```compile_fail
+//! #![deny(unused_variables)]
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! # fn main() {
+//! let strict = false;
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     // error: unused variable: `error`
+//!     catch error: ParseIntError if !strict => 1,
+//!     catch _ => 2,
+//! };
+//! # }
+//! ```
+//!
+//! Arms written as `catch &e: Type` catch the error by reference: they run with
+//! `&Type` and then leave the error to the following arms, as if it had not
+//! been caught. This is useful to log an error while still propagating it.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! let mut log = vec![];
+//! let result: Result<i32, _> = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch &error: ParseIntError => log.push(error.to_string()),
+//!     catch error: std::io::Error => 0,
+//! };
+//! assert!(result.is_err());
+//! assert_eq!(log, ["invalid digit found in string"]);
+//! ```
+//!
+//! Similarly, `catch mut e: Type` gets `&mut Type`, so the error can be
+//! enriched in place before it continues to the following arms, keeping its
+//! type and its allocation.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{error::Error, fmt};
+//! #[derive(Debug)]
+//! struct SyncError {
+//!     paths: Vec<String>,
+//! }
+//! # impl fmt::Display for SyncError {
+//! #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//! #         write!(f, "failed to sync {}", self.paths.join(", "))
+//! #     }
+//! # }
+//! # impl Error for SyncError {}
+//!
+//! let path = "/home";
+//! let result: Result<(), _> = catch! {
+//!     try {
+//!         Err(SyncError { paths: vec![] })?
+//!     }
+//!     catch mut error: SyncError => error.paths.push(path.into()),
+//! };
+//! let error = result.unwrap_err();
+//! assert_eq!(error.downcast_ref::<SyncError>().unwrap().paths, ["/home"]);
+//! ```
+//!
+//! Arms can also catch every error implementing a trait with `catch e: dyn Trait`,
+//! binding a `&dyn Trait`. Since a `dyn Error` can only be downcast to concrete
+//! types, the trait object has to implement [`ErrorCast`], usually through
+//! [`error_cast!`], which lists the error types implementing the trait.
+//! ```rust
+//! # use try_catch::{catch, error_cast};
+//! # use std::{io, num::ParseIntError};
+//! trait Retryable {
+//!     fn attempts(&self) -> u32;
+//! }
+//! impl Retryable for io::Error {
+//!     fn attempts(&self) -> u32 {
+//!         3
+//!     }
+//! }
+//! impl Retryable for ParseIntError {
+//!     fn attempts(&self) -> u32 {
+//!         1
+//!     }
+//! }
+//! error_cast!(dyn Retryable: io::Error, ParseIntError);
+//!
+//! let attempts: u32 = catch! {
+//!     try {
+//!         "ten".parse::<u32>()?
+//!     }
+//!     catch error: dyn Retryable => error.attempts(),
+//!     catch error => 0,
+//! };
+//! assert_eq!(attempts, 1);
+//! ```
+//!
+//! An arm that turns out not to be able to handle the error can give it up with
+//! `rethrow;`, which propagates the caught error as if no arm had matched it, or
+//! with `rethrow error;` to propagate another error instead, and either can be
+//! the body of an arrow arm, as in `catch error => rethrow,`. Since any arm
+//! using `rethrow` can fail, the expression stays a `Result` even with a
+//! wildcard arm.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{io, num::ParseIntError};
+//! let result: Result<i32, _> = catch! {
+//!     try {
+//!         "".parse::<i32>()?
+//!     }
+//!     catch error: ParseIntError {
+//!         if error.to_string().contains("empty") {
+//!             rethrow io::Error::new(io::ErrorKind::InvalidInput, "no number given");
+//!         }
+//!         0
+//!     }
+//!     catch error => rethrow,
+//! };
+//! assert_eq!(result.unwrap_err().to_string(), "no number given");
+//! ```
+//!
+//! `rethrow with message;` wraps the caught error in a [`Rethrown`] error
+//! displaying `message` instead, with the caught error as its source. String
+//! literals are formatted like `format!`, so they can capture variables.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! let user = 7;
+//! let result: Result<i32, _> = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch error: ParseIntError {
+//!         rethrow with "failed while syncing user {user}";
+//!     }
+//! };
+//! let error = result.unwrap_err();
+//! assert_eq!(error.to_string(), "failed while syncing user 7");
+//! assert!(error.source().unwrap().is::<ParseIntError>());
+//! ```
+//!
+//! `catch deep e: Type` also matches when an error of that type is the source
+//! of the error, or any error further down its `source()` chain. The chain is
+//! walked from the error itself inward, so the outermost error of the type is
+//! bound, by reference, while the error that was raised stays in its box, and
+//! is what `rethrow;` raises again.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{error::Error, fmt, num::ParseIntError};
+//! #[derive(Debug)]
+//! struct QueryError(ParseIntError);
+//! impl fmt::Display for QueryError {
+//!     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//!         write!(f, "query failed")
+//!     }
+//! }
+//! impl Error for QueryError {
+//!     fn source(&self) -> Option<&(dyn Error + 'static)> {
+//!         Some(&self.0)
+//!     }
+//! }
+//!
+//! let message = catch! {
+//!     try {
+//!         let id = "seven".parse::<i32>().map_err(QueryError)?;
+//!         id.to_string()
+//!     }
+//!     catch deep error: ParseIntError => format!("bad id: {}", error),
+//!     catch error => error.to_string(),
+//! };
+//! assert_eq!(message, "bad id: invalid digit found in string");
+//! ```
+//!
+//! Errors that are already boxed when they reach the try block, such as those
+//! of functions returning `Result<T, Box<dyn Error>>`, are matched by their
+//! contents: `?` moves the box as is rather than boxing it again. The same
+//! holds for a `Box<dyn Error + Send + Sync>`, which is kept as is in `send`
+//! invocations and coerced into a `Box<dyn Error>` in the others.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{error::Error, num::ParseIntError};
+//! fn parse(text: &str) -> Result<i32, Box<dyn Error>> {
+//!     Ok(text.parse::<i32>()?)
+//! }
+//! fn parse_send(text: &str) -> Result<i32, Box<dyn Error + Send + Sync>> {
+//!     Ok(text.parse::<i32>()?)
+//! }
+//!
+//! let number = catch! {
+//!     try {
+//!         parse("ten")?
+//!     }
+//!     catch error: ParseIntError => -1,
+//!     catch error => -2,
+//! };
+//! assert_eq!(number, -1);
+//! let number = catch! {
+//!     send try {
+//!         parse_send("ten")?
+//!     }
+//!     catch error: ParseIntError => -1,
+//!     catch error => -2,
+//! };
+//! assert_eq!(number, -1);
+//! let number = catch! {
+//!     try {
+//!         parse_send("ten")?
+//!     }
+//!     catch error: ParseIntError => -1,
+//!     catch error => -2,
+//! };
+//! assert_eq!(number, -1);
+//! ```
+//!
+//! Errors shared in an `Arc<dyn Error + Send + Sync>` are raised with `?` as
+//! the `Arc` itself, which typed arms cannot move the error out of. `deep`
+//! arms look through the `Arc` instead, and bind the shared error by reference.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{error::Error, io, sync::Arc};
+//! type SharedError = Arc<dyn Error + Send + Sync>;
+//!
+//! let error: SharedError = Arc::new(io::Error::new(io::ErrorKind::TimedOut, "too slow"));
+//! for shared in [error.clone(), error] {
+//!     let kind = catch! {
+//!         try {
+//!             Err::<(), SharedError>(shared)?;
+//!             None
+//!         }
+//!         catch deep error: io::Error => Some(error.kind()),
+//!         catch error => None,
+//!     };
+//!     assert_eq!(kind, Some(io::ErrorKind::TimedOut));
+//! }
+//! ```
+//!
+//! `catch panic payload { .. }` catches the panics of the try block as well,
+//! using [`std::panic::catch_unwind`], and binds them as a [`Panicked`] error,
+//! which has the panic message when there is one. Panics are only caught when
+//! the invocation has such an arm, and the try block is then asserted to be
+//! unwind safe. Panics of async try blocks cannot be caught this way, and with
+//! `panic = "abort"` there is nothing to catch.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::io;
+//! # std::panic::set_hook(Box::new(|_| {}));
+//! let status = catch! {
+//!     try {
+//!         let ports: Vec<u16> = vec![];
+//!         ports[0]
+//!     }
+//!     catch error: io::Error => 500,
+//!     catch panic payload {
+//!         assert!(payload.message().unwrap().contains("out of bounds"));
+//!         503
+//!     }
+//! };
+//! assert_eq!(status.unwrap(), 503);
+//! ```
+//!
+//! The binding can also be written in parentheses, as in C# or Java, either
+//! as `catch (error: Type)` or `catch (Type error)`, and mixed freely with the
+//! other spellings:
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{fmt, io, num::ParseIntError};
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     } catch (error: io::Error) {
+//!         1
+//!     } catch (fmt::Error error) {
+//!         2
+//!     } catch error: ParseIntError {
+//!         3
+//!     } catch (error) {
+//!         4
+//!     }
+//! };
+//! assert_eq!(number, 3);
+//! ```
+//!
+//! Outer attributes written before `catch` are forwarded to the arm, so
+//! `#[cfg(...)]` removes the whole arm and lint attributes such as
+//! `#[allow(unused_variables)]` apply to it. `#[cfg]` is rejected on the
+//! wildcard arm, since that arm decides the type of the expression.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     #[cfg(any())]
+//!     catch error: ParseIntError {
+//!         1
+//!     }
+//!     #[allow(unused_variables)]
+//!     catch error {
+//!         2
+//!     }
+//! };
+//! assert_eq!(number, 2);
+//! ```
+//!
+//! ## Finally
+//! A trailing `finally` block runs after the try block and the catch arms,
+//! whether the try block succeeded, an arm handled the error, or the error is
+//! propagated because no arm matched, and when a `return`, `break` or
+//! `continue` of the try block or of an arm, or a `?` in an arm, leaves the
+//! expression early (see [Control flow](#control-flow)). The value of the
+//! expression is still the value of the try block or of the arm that ran.
+//! ```rust
+//! # use try_catch::catch;
+//! let mut log = vec![];
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     } catch error {
+//!         0
+//!     } finally {
+//!         log.push("done")
+//!     }
+//! };
+//! assert_eq!(number, 0);
+//! assert_eq!(log, ["done"]);
+//! ```
+//!
+//! An arm leaving with `?`, `return` or `continue` still runs it.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{io, num::ParseIntError};
+//! fn total(texts: &[&str], log: &mut Vec<String>) -> Result<i32, io::Error> {
+//!     let mut total = 0;
+//!     for text in texts {
+//!         total += catch! {
+//!             try {
+//!                 text.parse::<i32>()?
+//!             }
+//!             catch _: ParseIntError if text.is_empty() => continue,
+//!             catch _: ParseIntError if *text == "stop" => return Ok(total),
+//!             catch _ => Err(io::Error::new(io::ErrorKind::InvalidData, *text))?,
+//!             finally {
+//!                 log.push(text.to_string());
+//!             }
+//!         };
+//!     }
+//!     Ok(total)
+//! }
+//! let mut log = vec![];
+//! assert_eq!(total(&["1", "", "2", "stop", "3"], &mut log).unwrap(), 3);
+//! assert_eq!(total(&["1", "x"], &mut log).unwrap_err().to_string(), "x");
+//! assert_eq!(log, ["1", "", "2", "stop", "1", "x"]);
+//! ```
+//!
+//! `finally` must be the last clause; the arms cannot follow it.
+//! ```compile_fail
+//! # use try_catch::catch;
+//! // error: `finally` must be the last clause, after the catch arms
+//! let number: i32 = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     } finally {
+//!         println!("done")
+//!     } catch error {
+//!         0
+//!     }
+//! };
+//! ```
+//!
+//! [`defer!`] runs statements when the enclosing scope exits instead, be it the
+//! try block of a `catch!`, where they run before the arms, or any other
+//! block, and [`defer_on_error!`] only does so until its guard is defused.
+//!
+//! ## Existing results
+//! When the fallible call has already happened, `match` runs the catch arms
+//! over a `Result` value instead of a try block. The success value passes
+//! through untouched, and errors that are not already boxed are converted
+//! into `Box<dyn Error>` before the arms are tried.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! let parsed: Result<i32, ParseIntError> = "ten".parse();
+//! let number: i32 = catch! {
+//!     match parsed {
+//!         catch error: ParseIntError {
+//!             -1
+//!         } catch error {
+//!             0
+//!         }
+//!     }
+//! };
+//! assert_eq!(number, -1);
+//! ```
+//!
+//! ## Error enums
+//! When every error is a single enum, `enum Type` before `try` skips the boxing
+//! and the arms become a plain `match` on its variants. Errors are converted with
+//! `?` as usual, and rustc checks that the arms are exhaustive, so the expression
+//! evaluates to the value of the try block rather than a `Result`.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! enum LookupError {
+//!     NotFound,
+//!     Parse(ParseIntError),
+//! }
+//! impl From<ParseIntError> for LookupError {
+//!     fn from(error: ParseIntError) -> Self {
+//!         LookupError::Parse(error)
+//!     }
+//! }
+//!
+//! fn lookup(key: &str) -> Result<&'static str, LookupError> {
+//!     match key {
+//!         "answer" => Ok("forty-two"),
+//!         _ => Err(LookupError::NotFound),
+//!     }
+//! }
+//!
+//! let number: i32 = catch! {
+//!     enum LookupError try {
+//!         lookup("answer")?.parse::<i32>()?
+//!     } catch LookupError::NotFound {
+//!         0
+//!     } catch LookupError::Parse(error) {
+//!         -1
+//!     }
+//! };
+//! assert_eq!(number, -1);
+//! ```
+//!
+//! Since nothing is boxed, `enum` invocations do not allocate, and they are
+//! available on `no_std` targets with `default-features = false`, where the
+//! rest of the crate, which relies on `Box<dyn Error>`, is left out. The
+//! `alloc` feature brings it back on `no_std` targets with an allocator.
+//!
+//! ## Error carriers
+//! Errors reach the arms as a `Box<dyn Error>`. With `error = Type`, they are
+//! carried as `Type` instead, such as `anyhow::Error` or `eyre::Report`, and
+//! errors that no arm handles are returned as one. The carrier needs the
+//! downcasting methods of those types: `is`, `downcast` returning the error by
+//! value, `downcast_ref` and `downcast_mut`, as well as `From` for the errors
+//! raised with `?`, and a `Deref` to the error.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{error::Error, fmt, num::ParseIntError, ops::{Deref, DerefMut}};
+//! # #[derive(Debug)]
+//! # pub struct Report(Box<dyn Error + Send + Sync>);
+//! # impl<E: Error + Send + Sync + 'static> From<E> for Report {
+//! #     fn from(error: E) -> Self { Report(Box::new(error)) }
+//! # }
+//! # impl Report {
+//! #     pub fn is<E: Error + 'static>(&self) -> bool { self.0.is::<E>() }
+//! #     pub fn downcast<E: Error + 'static>(self) -> Result<E, Self> {
+//! #         self.0.downcast().map(|error| *error).map_err(Report)
+//! #     }
+//! #     pub fn downcast_ref<E: Error + 'static>(&self) -> Option<&E> { self.0.downcast_ref() }
+//! #     pub fn downcast_mut<E: Error + 'static>(&mut self) -> Option<&mut E> { self.0.downcast_mut() }
+//! # }
+//! # impl Deref for Report {
+//! #     type Target = dyn Error + Send + Sync;
+//! #     fn deref(&self) -> &Self::Target { &*self.0 }
+//! # }
+//! # impl DerefMut for Report {
+//! #     fn deref_mut(&mut self) -> &mut Self::Target { &mut *self.0 }
+//! # }
+//! # impl fmt::Display for Report {
+//! #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { self.0.fmt(f) }
+//! # }
+//! // `Report` has the same downcasting methods as `anyhow::Error`.
+//! fn port(text: &str) -> Result<u16, Report> {
+//!     catch! {
+//!         error = Report try {
+//!             if text.is_empty() {
+//!                 throw fmt::Error;
+//!             }
+//!             text.parse()?
+//!         }
+//!         catch _: ParseIntError => 8080,
+//!     }
+//! }
+//! assert_eq!(port("http").unwrap(), 8080);
+//! assert!(port("").unwrap_err().is::<fmt::Error>());
+//! ```
+//!
+//! `send` carries errors as a `Box<dyn Error + Send + Sync>`, so that the
+//! result can cross threads. Raising an error that is not `Send` and `Sync` is
+//! then a compile error at the `?` or `throw` that raises it.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{io, thread};
+//! let handle = thread::spawn(|| catch! {
+//!     send try {
+//!         "80".parse::<u16>()?
+//!     }
+//!     catch error: io::Error => 0,
+//! });
+//! assert_eq!(handle.join().unwrap().unwrap(), 80);
+//! ```
+//!
+//! ## Borrowed errors
+//! Errors are boxed as a `Box<dyn Error>`, which cannot hold an error that
+//! borrows data, such as a parse error borrowing from its input. Raising one
+//! is a compile error at the `?` or `throw` that raises it:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::fmt;
+//! #[derive(Debug)]
+//! struct ParseError<'a>(&'a str);
+//! # impl fmt::Display for ParseError<'_> {
+//! #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "unexpected `{}`", self.0) }
+//! # }
+//! # impl std::error::Error for ParseError<'_> {}
+//!
+//! fn parse(input: &str) -> Result<u16, ParseError<'_>> {
+//!     input.parse().map_err(|_| ParseError(input))
+//! }
+//!
+//! fn port(input: &str) -> u16 {
+//!     catch! {
+//!         try {
+//!             // error: borrowed data escapes outside of function
+//!             parse(input)?
+//!         }
+//!         catch _ => 8080,
+//!     }
+//! }
+//! ```
+//! `map_err = ..` before `try` converts the error of each `?` and `throw` of
+//! the try block before it is boxed, which can turn borrowed errors into
+//! owned ones. It is called with every error raised in the block, so a
+//! generic function or a trait method can convert each type differently:
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::fmt;
+//! #[derive(Debug)]
+//! struct ParseError<'a>(&'a str);
+//! # impl fmt::Display for ParseError<'_> {
+//! #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "unexpected `{}`", self.0) }
+//! # }
+//! # impl std::error::Error for ParseError<'_> {}
+//!
+//! #[derive(Debug)]
+//! struct OwnedParseError(String);
+//! # impl fmt::Display for OwnedParseError {
+//! #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "unexpected `{}`", self.0) }
+//! # }
+//! # impl std::error::Error for OwnedParseError {}
+//!
+//! impl From<ParseError<'_>> for OwnedParseError {
+//!     fn from(error: ParseError<'_>) -> Self {
+//!         OwnedParseError(error.0.to_owned())
+//!     }
+//! }
+//!
+//! fn parse(input: &str) -> Result<u16, ParseError<'_>> {
+//!     input.parse().map_err(|_| ParseError(input))
+//! }
+//!
+//! fn port(input: &str) -> u16 {
+//!     catch! {
+//!         map_err = OwnedParseError::from try {
+//!             parse(input)?
+//!         }
+//!         catch error: OwnedParseError if error.0 == "https" => 443,
+//!         catch _ => 80,
+//!     }
+//! }
+//! assert_eq!(port("8080"), 8080);
+//! assert_eq!(port("https"), 443);
+//! assert_eq!(port("ftp"), 80);
+//! ```
+//!
+//! A [`PoisonError`](std::sync::PoisonError) borrows the lock through the guard
+//! it holds, so `mutex.lock()?` is rejected the same way. Converting it into a
+//! [`Poisoned`] error drops the guard, and the arms can catch it by type:
+//! ```rust
+//! # use try_catch::{catch, Poisoned};
+//! # use std::{sync::{Arc, Mutex}, thread};
+//! # std::panic::set_hook(Box::new(|_| {}));
+//! let counter = Arc::new(Mutex::new(0));
+//! let poisoner = Arc::clone(&counter);
+//! thread::spawn(move || {
+//!     let _guard = poisoner.lock().unwrap();
+//!     panic!("failed while holding the lock");
+//! })
+//! .join()
+//! .unwrap_err();
+//!
+//! let count = catch! {
+//!     try {
+//!         let mut count = counter.lock().map_err(Poisoned::from)?;
+//!         *count += 1;
+//!         *count
+//!     }
+//!     catch _: Poisoned {
+//!         counter.clear_poison();
+//!         -1
+//!     }
+//!     catch _ => 0,
+//! };
+//! assert_eq!(count, -1);
+//! assert_eq!(*counter.lock().unwrap(), 0);
+//! ```
+//!
+//! ## Channel errors
+//! The errors of sending on a channel, such as
+//! [`SendError<T>`](std::sync::mpsc::SendError), hold the value that could not
+//! be sent. An arm catching the error with the type of the value gets it back:
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::sync::mpsc::{channel, sync_channel, SendError, TrySendError};
+//! #[derive(Debug, PartialEq)]
+//! struct Job(u32);
+//!
+//! let (sender, receiver) = channel();
+//! drop(receiver);
+//! let unsent = catch! {
+//!     try {
+//!         sender.send(Job(1))?;
+//!         None
+//!     }
+//!     catch error: SendError<Job> => Some(error.0),
+//!     catch _ => None,
+//! };
+//! assert_eq!(unsent, Some(Job(1)));
+//!
+//! let (sender, _receiver) = sync_channel(0);
+//! let unsent = catch! {
+//!     try {
+//!         sender.try_send(Job(2))?;
+//!         None
+//!     }
+//!     catch TrySendError::Full(job): TrySendError<Job> => Some(job),
+//!     catch _ => None,
+//! };
+//! assert_eq!(unsent, Some(Job(2)));
+//! ```
+//! As with any error, the value has to be `'static` to be boxed, so sending
+//! borrowed values is rejected at the `?` as described in
+//! [Borrowed errors](#borrowed-errors), and an arm naming a borrowed value
+//! is rejected at its type:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::sync::mpsc::{channel, SendError};
+//! fn send<'a>(name: &'a str) -> Option<&'a str> {
+//!     let (sender, receiver) = channel::<String>();
+//!     drop(receiver);
+//!     catch! {
+//!         try {
+//!             sender.send(name.to_owned())?;
+//!             None
+//!         }
+//!         // error: lifetime may not live long enough
+//!         catch error: SendError<&'a str> => Some(error.0),
+//!         catch _ => None,
+//!     }
+//! }
+//! ```
+//!
+//! ## String errors
+//! Errors that are plain strings, as in `Result<T, String>`, can be raised
+//! with `?` and `throw` like any other error, and arms catching `String` or
+//! `&'static str` catch them back. Such arms bind the string itself:
+//! ```rust
+//! # use try_catch::catch;
+//! fn check(name: &str) -> Result<(), String> {
+//!     if name.is_empty() {
+//!         return Err(format!("the name is empty"));
+//!     }
+//!     Ok(())
+//! }
+//!
+//! let register = |name: &str| catch! {
+//!     try {
+//!         check(name)?;
+//!         if name == "root" {
+//!             throw "the name is taken";
+//!         }
+//!         String::new()
+//!     }
+//!     catch message: &'static str => message.to_owned(),
+//!     catch message: String => message,
+//!     catch _ => String::new(),
+//! };
+//! assert_eq!(register(""), "the name is empty");
+//! assert_eq!(register("root"), "the name is taken");
+//! ```
+//! Strings are boxed as a [`DisplayError`] of them, which is what the arms
+//! look for. Only the strings raised in the try block itself are boxed this
+//! way, those boxed by the functions it calls stay out of reach of the arms.
+//!
+//! Other values that only implement `Display` cannot be boxed as errors,
+//! which is reported at the `?` that raises them:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::fmt;
+//! struct Status(u16);
+//! # impl fmt::Display for Status {
+//! #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "status {}", self.0) }
+//! # }
+//!
+//! fn fetch() -> Result<String, Status> {
+//!     Err(Status(404))
+//! }
+//!
+//! let page = catch! {
+//!     try {
+//!         // error: `?` couldn't convert the error to `Box<dyn std::error::Error>`
+//!         fetch()?
+//!     }
+//!     catch _ => String::new(),
+//! };
+//! ```
+//! Wrapped in a `DisplayError`, they are raised and caught as one:
+//! ```rust
+//! # use try_catch::{catch, DisplayError};
+//! # use std::fmt;
+//! # struct Status(u16);
+//! # impl fmt::Display for Status {
+//! #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "status {}", self.0) }
+//! # }
+//! # fn fetch() -> Result<String, Status> {
+//! #     Err(Status(404))
+//! # }
+//! let page = catch! {
+//!     try {
+//!         fetch().map_err(DisplayError)?
+//!     }
+//!     catch DisplayError(Status(404)): DisplayError<Status> => "not found".to_owned(),
+//!     catch _ => String::new(),
+//! };
+//! assert_eq!(page, "not found");
+//! ```
+//!
+//! ## None values
+//! `?` can be applied to an `Option` in a try block as well. A `None` leaves
+//! the block with a [`NoneError`], which `catch none` arms catch, as do arms
+//! catching the `NoneError` type:
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::collections::HashMap;
+//! let ports = HashMap::from([("http", "80"), ("ftp", "twenty-one")]);
+//! let port = |scheme: &str| catch! {
+//!     try {
+//!         let port = ports.get(scheme)?;
+//!         port.parse::<u16>()?
+//!     }
+//!     catch none if scheme == "https" => 443,
+//!     catch none => 0,
+//!     catch _ => 1,
+//! };
+//! assert_eq!(port("http"), 80);
+//! assert_eq!(port("https"), 443);
+//! assert_eq!(port("ssh"), 0);
+//! assert_eq!(port("ftp"), 1);
+//! ```
+//! Followed by its body or a guard, `none` is taken as this arm rather than as
+//! the name of a wildcard arm's error. With `error = ..` or `enum`, the carrier
+//! has to implement `From<NoneError>`.
+//!
+//! ## Exhaustive arms
+//! `exhaustive(Type, ..)` before `try` declares the error types the try block
+//! can fail with, and makes it a compile error for the typed arms to miss one
+//! of them or to catch a type that is not listed. Types are compared as
+//! written, so they have to be spelled the same way in both places. Arms with
+//! a guard, a kind filter or catching by reference do not count as handling
+//! a type.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{fs, io, num::ParseIntError};
+//! let result: Result<i32, _> = catch! {
+//!     exhaustive(io::Error, ParseIntError) try {
+//!         fs::read_to_string("/definitely/not/here")?.trim().parse::<i32>()?
+//!     }
+//!     catch error: io::Error => 0,
+//!     catch error: ParseIntError => -1,
+//! };
+//! assert_eq!(result.unwrap(), 0);
+//! ```
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::{fs, io, num::ParseIntError};
+//! // error: unhandled error types: `ParseIntError`
+//! let result: Result<i32, _> = catch! {
+//!     exhaustive(io::Error, ParseIntError) try {
+//!         fs::read_to_string("/definitely/not/here")?.trim().parse::<i32>()?
+//!     }
+//!     catch error: io::Error => 0,
+//! };
+//! ```
+//!
+//! ## Strict mode
+//! Without a wildcard arm, errors that no arm handles are returned and the
+//! expression is a `Result`. Writing `strict` before `try` turns a missing
+//! wildcard arm into a compile error instead, for code where every error must
+//! be handled explicitly.
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! // error: `strict` requires a wildcard arm such as `catch error { .. }`
+//! let number = catch! {
+//!     strict try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch error: ParseIntError => 0,
+//! };
+//! ```
+//!
+//! `no_wildcard` does the opposite, rejecting untyped arms such as
+//! `catch error { .. }`, which is useful in library code where swallowing
+//! unknown failures is rarely intended.
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! // error: `no_wildcard` forbids untyped catch arms
+//! let number: i32 = catch! {
+//!     no_wildcard try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch error: ParseIntError => 0,
+//!     catch error => 1,
+//! };
+//! ```
+//!
+//! ## Unmatched errors
+//! With `propagate`, errors that no arm handles are returned from the enclosing
+//! function, converted with [`From`] like `?` does, and the expression evaluates
+//! to the value of the try block or the arms. Each error raised in the try block
+//! must then convert into the function's error type, as it would with a `?`
+//! written in the function itself, and it is returned as it was raised rather
+//! than boxed. Arms without a type see the error as a `dyn Error`.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{error::Error, io, num::ParseIntError};
+//! fn port(text: &str) -> Result<u16, Box<dyn Error>> {
+//!     let port = catch! {
+//!         propagate try {
+//!             if text.is_empty() {
+//!                 throw io::Error::other("no port given");
+//!             }
+//!             text.parse()?
+//!         }
+//!         catch _: ParseIntError => 8080,
+//!     };
+//!     Ok(port)
+//! }
+//! assert_eq!(port("80").unwrap(), 80);
+//! assert_eq!(port("http").unwrap(), 8080);
+//! assert!(port("").is_err());
+//! ```
+//!
+//! The function's error type need not be boxed, and a `finally` block also runs
+//! when an error is returned.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{io, num::ParseIntError};
+//! #[derive(Debug)]
+//! enum ConfigError {
+//!     Io(io::Error),
+//!     Parse(ParseIntError),
+//! }
+//! impl From<io::Error> for ConfigError {
+//!     fn from(error: io::Error) -> Self {
+//!         ConfigError::Io(error)
+//!     }
+//! }
+//! impl From<ParseIntError> for ConfigError {
+//!     fn from(error: ParseIntError) -> Self {
+//!         ConfigError::Parse(error)
+//!     }
+//! }
+//! fn workers(text: &str, log: &mut Vec<&str>) -> Result<u32, ConfigError> {
+//!     let workers = catch! {
+//!         propagate try {
+//!             if text.is_empty() {
+//!                 throw io::Error::other("no worker count");
+//!             }
+//!             text.parse()?
+//!         }
+//!         catch _: ParseIntError if text == "auto" => 4,
+//!         finally {
+//!             log.push("read");
+//!         }
+//!     };
+//!     Ok(workers)
+//! }
+//! let mut log = vec![];
+//! assert_eq!(workers("auto", &mut log).unwrap(), 4);
+//! assert!(matches!(workers("", &mut log), Err(ConfigError::Io(_))));
+//! assert!(matches!(workers("many", &mut log), Err(ConfigError::Parse(_))));
+//! assert_eq!(log, ["read", "read", "read"]);
+//! ```
+//!
+//! `expect("..")` panics on those errors instead, with the message followed by
+//! the error and a `caused by: ..` line for each of its sources, as in the
+//! report of [`#[catch::main]`](#main-functions), which suits tests and small
+//! binaries. It cannot be combined with a wildcard arm, since nothing would be
+//! left to panic on.
+//! ```rust,should_panic
+//! # use try_catch::catch;
+//! # use std::{io, num::ParseIntError};
+//! // panics with "bad seed: no seed file"
+//! let seed: u64 = catch! {
+//!     expect("bad seed") try {
+//!         if true {
+//!             throw io::Error::other("no seed file");
+//!         }
+//!         "42".parse()?
+//!     }
+//!     catch _: ParseIntError => 0,
+//! };
+//! ```
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{error::Error, fmt, io, num::ParseIntError, panic};
+//! #[derive(Debug)]
+//! struct SeedError(ParseIntError);
+//! # impl fmt::Display for SeedError {
+//! #     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+//! #         f.write_str("invalid seed")
+//! #     }
+//! # }
+//! impl Error for SeedError {
+//!     fn source(&self) -> Option<&(dyn Error + 'static)> {
+//!         Some(&self.0)
+//!     }
+//! }
+//! let panic = panic::catch_unwind(|| {
+//!     catch! {
+//!         expect("bad seed") try {
+//!             "forty-two".parse::<u64>().map_err(SeedError)?
+//!         }
+//!         catch _: io::Error => 0,
+//!     }
+//! })
+//! .unwrap_err();
+//! assert_eq!(
+//!     panic.downcast_ref::<String>().unwrap(),
+//!     "bad seed: invalid seed\ncaused by: invalid digit found in string",
+//! );
+//! ```
+//!
+//! With `or_default`, they are replaced by the default value of the success
+//! type, while the arms still handle the errors that need their own treatment.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{io, fs};
+//! #[derive(Default, Debug, PartialEq)]
+//! struct Config {
+//!     verbose: bool,
+//! }
+//! let config: Config = catch! {
+//!     or_default try {
+//!         let text = fs::read_to_string("missing.toml")?;
+//!         Config { verbose: text.contains("verbose") }
+//!     }
+//!     catch error: io::Error if error.kind() == io::ErrorKind::PermissionDenied => {
+//!         eprintln!("cannot read the config: {}", error);
+//!         Config::default()
+//!     }
+//! };
+//! assert_eq!(config, Config::default());
+//! ```
+//!
+//! ## Errors in arms
+//! The catch arms run as ordinary code of the enclosing function, so a `?` in
+//! an arm returns its error from that function, converted with [`From`], rather
+//! than being caught by the same `catch!`. This suits fallbacks that can fail
+//! too:
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! fn port(text: &str, fallback: &str) -> Result<u16, ParseIntError> {
+//!     let port = catch! {
+//!         try {
+//!             text.parse::<u16>()?
+//!         } catch error: ParseIntError {
+//!             fallback.parse::<u16>()?
+//!         } catch error {
+//!             0
+//!         }
+//!     };
+//!     Ok(port)
+//! }
+//! assert_eq!(port("http", "8080"), Ok(8080));
+//! assert!(port("http", "https").is_err());
+//! ```
+//! In `lazy` expressions, the arms run inside the future instead, where `?`
+//! cannot reach the function that created it.
+//!
+//! Likewise, arms can leave with `return`, `break` or `continue`, or never
+//! finish with `panic!` or `std::process::exit`. Such arms fit whatever type
+//! the other arms and the try block have.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{error::Error, io, num::ParseIntError};
+//! fn parse(text: &str) -> Result<i32, Box<dyn Error>> {
+//!     let number: i32 = catch! {
+//!         try {
+//!             text.parse::<i32>()?
+//!         } catch error: io::Error {
+//!             return Err(error.into());
+//!         } catch error {
+//!             return Err(error);
+//!         }
+//!     };
+//!     Ok(number)
+//! }
+//! assert!(parse("ten").is_err());
+//!
+//! fn parse_or_exit(text: &str) -> i32 {
+//!     catch! {
+//!         try {
+//!             text.parse::<i32>()?
+//!         } catch error: ParseIntError {
+//!             panic!("fatal: {}", error)
+//!         } catch error {
+//!             std::process::exit(1)
+//!         }
+//!     }
+//! }
+//! assert_eq!(parse_or_exit("10"), 10);
+//!
+//! let mut total = 0;
+//! for text in ["1", "two", "3"] {
+//!     total += catch! {
+//!         try {
+//!             text.parse::<i32>()?
+//!         } catch error: ParseIntError {
+//!             continue;
+//!         } catch error {
+//!             std::process::exit(1)
+//!         }
+//!     };
+//! }
+//! assert_eq!(total, 4);
+//!
+//! // Without a wildcard arm, the expression is a `Result`.
+//! for text in ["1", "two"] {
+//!     let number: Result<i32, _> = catch! {
+//!         try {
+//!             text.parse::<i32>()?
+//!         } catch error: ParseIntError {
+//!             break;
+//!         }
+//!     };
+//!     assert_eq!(number.unwrap(), 1);
+//! }
+//! ```
+//!
+//! ## Optional results
+//! [`catch_opt!`] takes the same arms but evaluates to an `Option`: `Some` with
+//! the value of the try block, or `None` when it fails. Arms run for their side
+//! effects, and can replace the error with a value using `resume value;`, or
+//! `=> resume value` as the body of an arrow arm.
+//! ```rust
+//! # use try_catch::catch_opt;
+//! # use std::num::ParseIntError;
+//! fn parse(text: &str) -> Option<i32> {
+//!     catch_opt! {
+//!         try {
+//!             text.parse::<i32>()?
+//!         }
+//!         catch _: ParseIntError if text == "none" => resume 0,
+//!         catch error: ParseIntError {
+//!             eprintln!("not a number: {}", error);
+//!             if text == "zero" {
+//!                 resume 0;
+//!             }
+//!         }
+//!     }
+//! }
+//! assert_eq!(parse("7"), Some(7));
+//! assert_eq!(parse("zero"), Some(0));
+//! assert_eq!(parse("none"), Some(0));
+//! assert_eq!(parse("seven"), None);
+//! ```
+//!
+//! ## Results
+//! [`catch_expr!`] takes the same arms as `catch!` but always evaluates to the
+//! `Result`, even with a wildcard arm: the try block and the arms produce its
+//! `Ok` value, and the errors that no arm handles, or that an arm rethrows,
+//! are its `Err`. Its type then does not depend on the arms, which makes it
+//! predictable when followed by `?`.
+//! ```rust
+//! # use try_catch::catch_expr;
+//! # use std::{error::Error, num::ParseIntError};
+//! fn port(text: &str) -> Result<u16, Box<dyn Error>> {
+//!     let port = catch_expr! {
+//!         try {
+//!             text.parse::<u16>()?
+//!         }
+//!         catch _: ParseIntError if text.is_empty() => 8080,
+//!         catch error: ParseIntError {
+//!             rethrow;
+//!         }
+//!         catch _ => 80,
+//!     }?;
+//!     Ok(port)
+//! }
+//! assert_eq!(port("443").unwrap(), 443);
+//! assert_eq!(port("").unwrap(), 8080);
+//! assert!(port("http").unwrap_err().is::<ParseIntError>());
+//! ```
+//! `propagate`, `expect` and `or_default`, which take the value out of the
+//! `Result`, and `enum`, whose arms are a plain `match`, cannot be used with it.
+//!
+//! ## Translating errors
+//! With `into Type`, the arms translate errors instead of handling them: their
+//! values are errors of type `Type`, and the expression is a `Result` with that
+//! error type. A wildcard arm is required, so that every error is translated.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{io, num::ParseIntError};
+//! #[derive(Debug)]
+//! enum AppError {
+//!     Storage(io::Error),
+//!     BadPayload(ParseIntError),
+//!     Other(String),
+//! }
+//!
+//! fn load(payload: &str) -> Result<u32, AppError> {
+//!     catch! {
+//!         into AppError try {
+//!             if payload.is_empty() {
+//!                 throw io::Error::other("nothing stored");
+//!             }
+//!             payload.parse()?
+//!         }
+//!         catch error: io::Error => AppError::Storage(error),
+//!         catch error: ParseIntError => AppError::BadPayload(error),
+//!         catch error => AppError::Other(error.to_string()),
+//!     }
+//! }
+//! assert!(matches!(load("12"), Ok(12)));
+//! assert!(matches!(load(""), Err(AppError::Storage(_))));
+//! assert!(matches!(load("twelve"), Err(AppError::BadPayload(_))));
+//! ```
+//!
+//! ## Resources
+//! Like Java's try-with-resources, values acquired with `try with` are scoped
+//! to the try block. Failures while acquiring them flow into the catch arms like
+//! any other `?`, and they are dropped in reverse order as soon as the try block
+//! is left, before any catch arm runs. Resources that need an explicit cleanup
+//! step can implement [`Resource`] and be wrapped in [`Closing`].
+//! ```rust
+//! # use try_catch::{catch, Closing, Resource};
+//! # use std::{cell::RefCell, fs::File};
+//! struct Connection<'a>(&'a RefCell<Vec<&'static str>>);
+//!
+//! impl Resource for Connection<'_> {
+//!     fn close(&mut self) {
+//!         self.0.borrow_mut().push("closed");
+//!     }
+//! }
+//!
+//! let log = RefCell::new(vec![]);
+//! let number: i32 = catch! {
+//!     try with _conn = Closing::new(Connection(&log)), _file = File::open("missing.txt")? {
+//!         1
+//!     } catch error {
+//!         log.borrow_mut().push("caught");
+//!         0
+//!     }
+//! };
+//! assert_eq!(number, 0);
+//! assert_eq!(*log.borrow(), ["closed", "caught"]);
+//! ```
+//!
+//! ## Fallbacks
+//! Further blocks can be chained after the try block with `or`. Each one only
+//! runs if the previous one failed, and the catch arms only see the error of
+//! the last block that ran. The error of the previous block can be bound by
+//! writing a name after `or`.
+//! ```rust
+//! # use try_catch::catch;
+//! let mut failures = vec![];
+//! let number: i32 = catch! {
+//!     try {
+//!         "cached".parse::<i32>()?
+//!     } or error {
+//!         failures.push(error.to_string());
+//!         "fetched".parse::<i32>()?
+//!     } or {
+//!         "3".parse::<i32>()?
+//!     } catch error {
+//!         0
+//!     }
+//! };
+//! assert_eq!(number, 3);
+//! assert_eq!(failures, ["invalid digit found in string"]);
+//! ```
+//!
+//! ## Timeout
+//! Async try blocks can be given a deadline with `try timeout duration`. When it
+//! elapses first, the try block is cancelled and an [`Elapsed`] error flows
+//! through the catch arms like any other error. As for retries, the deadline is
+//! awaited with the `sleep` function, or `tokio::time::sleep` with the `tokio`
+//! feature.
+//! ```rust
+//! # use try_catch::{catch, Elapsed};
+//! # use std::{future, time::Duration};
+//! async fn sleep(duration: Duration) {
+//!     // e.g. tokio::time::sleep(duration).await
+//! }
+//!
+//! async fn fetch() -> i32 {
+//!     catch! {
+//!         try timeout Duration::from_secs(5) sleep sleep {
+//!             future::pending::<()>().await;
+//!             1
+//!         } catch error: Elapsed {
+//!             0
+//!         }
+//!     }
+//!     .unwrap()
+//! }
+//! # fn block_on<F: future::Future>(future: F) -> F::Output {
+//! #     use std::{sync::Arc, task::{Context, Poll, Wake}};
+//! #     struct Noop;
+//! #     impl Wake for Noop { fn wake(self: Arc<Self>) {} }
+//! #     let waker = Arc::new(Noop).into();
+//! #     let mut future = Box::pin(future);
+//! #     loop {
+//! #         if let Poll::Ready(output) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+//! #             return output;
+//! #         }
+//! #     }
+//! # }
+//! assert_eq!(block_on(fetch()), 0);
+//! ```
+//!
+//! ## Context
+//! A `context` clause placed before the catch arms wraps the errors that no arm
+//! handled in a [`ContextError`], which prefixes their message with the given
+//! context and keeps the original error as its `source()`. The context
+//! expression is only evaluated when an error is propagated.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::io;
+//! let result: Result<i32, _> = catch! {
+//!     try {
+//!         "ten".parse::<i32>()?
+//!     } context "loading user config"
+//!     catch error: io::Error {
+//!         0
+//!     }
+//! };
+//! let error = result.unwrap_err();
+//! assert_eq!(error.to_string(), "loading user config: invalid digit found in string");
+//! assert!(error.source().unwrap().is::<std::num::ParseIntError>());
+//! ```
+//!
+//! ## Locations
+//! Writing `located` before `try` wraps the errors that no arm handles in a
+//! [`Located`] error, which appends the file and line of the `catch!` to their
+//! message, so that an error propagated through several layers tells which
+//! `catch!` it escaped from. The original error stays its `source()`, where
+//! `deep` arms find it.
+//! ```rust
+//! # use try_catch::{catch, Located};
+//! # use std::{error::Error, io, num::ParseIntError};
+//! fn load(text: &str) -> Result<i32, Box<dyn Error>> {
+//!     catch! {
+//!         located try {
+//!             text.parse::<i32>()?
+//!         } catch error: io::Error {
+//!             0
+//!         }
+//!     }
+//! }
+//!
+//! let error = load("ten").unwrap_err();
+//! let location = error.downcast_ref::<Located>().unwrap().location();
+//! assert_eq!(
+//!     error.to_string(),
+//!     format!("invalid digit found in string at {}:{}", location.file(), location.line()),
+//! );
+//!
+//! let number = catch! {
+//!     try {
+//!         load("ten")?
+//!     }
+//!     catch deep error: ParseIntError => -1,
+//!     catch error => -2,
+//! };
+//! assert_eq!(number, -1);
+//! ```
+//!
+//! Like `context`, it needs errors carried as a `Box<dyn Error>`:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::io;
+//! // error: `located` needs errors carried as `Box<dyn Error>`, it cannot be used with `send` or `error = ..`
+//! let result: Result<i32, _> = catch! {
+//!     send located try {
+//!         "ten".parse::<i32>()?
+//!     }
+//!     catch error: io::Error => 0,
+//! };
+//! ```
+//!
+//! ## Backtraces
+//! Writing `traced` before `try` wraps the errors that reach an untyped arm or
+//! that no arm handles in a [`Traced`] error, carrying the backtrace of the
+//! `catch!` they went through. As with [`Backtrace::capture`], the backtrace is
+//! only captured when the `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` environment
+//! variables ask for it. The wrapper has the message of the original error,
+//! which stays its `source()`, and the typed arms of the `catch!` invocations
+//! it is raised into see through it. Code downcasting the error itself has to
+//! use `source()`, or [`Traced::into_source`]. With `propagate`, traced errors
+//! are returned boxed, so the function's error type must implement
+//! `From<Box<dyn Error>>`.
+//!
+//! [`backtrace`] finds the backtrace of an error or of one of its sources.
+//! ```rust
+//! # use try_catch::catch;
+//! let message = catch! {
+//!     traced try {
+//!         "ten".parse::<i32>()?.to_string()
+//!     } catch error {
+//!         match try_catch::backtrace(&*error) {
+//!             Some(backtrace) => format!("{}\n{}", error, backtrace),
+//!             None => error.to_string(),
+//!         }
+//!     }
+//! };
+//! assert!(message.starts_with("invalid digit found in string"));
+//! ```
+//!
+//! [`Backtrace::capture`]: std::backtrace::Backtrace::capture
+//!
+//! ## Cleanup
+//! A `cleanup` block placed before the catch arms runs right after the try
+//! block, whether it succeeded or not, and before any catch arm. Unlike `Drop`,
+//! it can `.await` when the macro is used in async code, which makes it the
+//! place for graceful asynchronous shutdowns.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::io;
+//! async fn recv() -> io::Result<i32> {
+//!     Err(io::Error::new(io::ErrorKind::BrokenPipe, "stream reset"))
+//! }
+//!
+//! struct Stream {
+//!     open: bool,
+//! }
+//!
+//! impl Stream {
+//!     async fn close(&mut self) {
+//!         self.open = false;
+//!     }
+//! }
+//!
+//! async fn first_message(stream: &mut Stream) -> i32 {
+//!     catch! {
+//!         try {
+//!             recv().await?
+//!         } cleanup {
+//!             stream.close().await;
+//!         } catch error: io::Error {
+//!             assert!(!stream.open);
+//!             0
+//!         } catch error {
+//!             -1
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Neither `cleanup` nor the arms run if the future awaiting an async try block
+//! is dropped before the block completes, as when a timeout or `select!` gives
+//! up on it. An `on_cancel` block placed after `cleanup` runs in that case, from
+//! a guard that is dropped along with the future. It borrows what it uses for
+//! as long as the try block runs, like a closure.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{cell::Cell, future::{self, Future}, pin::pin, sync::Arc, task::{Context, Wake}};
+//! async fn recv(connected: bool) -> std::io::Result<i32> {
+//!     if !connected {
+//!         future::pending::<()>().await;
+//!     }
+//!     Ok(1)
+//! }
+//!
+//! async fn first_message(connected: bool, cancelled: &Cell<u32>) -> i32 {
+//!     catch! {
+//!         try {
+//!             recv(connected).await?
+//!         } on_cancel {
+//!             cancelled.set(cancelled.get() + 1);
+//!         } catch error {
+//!             -1
+//!         }
+//!     }
+//! }
+//! # struct Noop;
+//! # impl Wake for Noop { fn wake(self: Arc<Self>) {} }
+//! # let waker = Arc::new(Noop).into();
+//!
+//! let cancelled = Cell::new(0);
+//! {
+//!     let mut future = pin!(first_message(false, &cancelled));
+//!     assert!(future.as_mut().poll(&mut Context::from_waker(&waker)).is_pending());
+//!     assert_eq!(cancelled.get(), 0);
+//! }
+//! assert_eq!(cancelled.get(), 1);
+//!
+//! let mut future = pin!(first_message(true, &cancelled));
+//! assert!(future.as_mut().poll(&mut Context::from_waker(&waker)).is_ready());
+//! assert_eq!(cancelled.get(), 1);
+//! ```
+//! `on_cancel` can only be used with async try blocks:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! catch! {
+//!     try {
+//!         "3".parse::<i32>()?
+//!     } on_cancel {
+//!         println!("cancelled");
+//!     } catch error {
+//!         0
+//!     }
+//! };
+//! ```
+//!
+//! ## Async
+//! Try blocks that contain `.await` are run inside an async block, so `catch!`
+//! can be used in async functions. Whether a try block is async is guessed by
+//! looking for `.await` in it, outside of the async blocks and closures it
+//! creates. Names such as `await_count` or `r#await` do not count.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::future::Future;
+//! struct Options {
+//!     r#await: bool,
+//! }
+//!
+//! fn deferred(text: &'static str) -> impl Future<Output = i32> {
+//!     let parsed = async move { text.parse::<i32>() };
+//!     let options = Options { r#await: true };
+//!     catch! {
+//!         try {
+//!             let await_timeout: u64 = "5".parse()?;
+//!             let future = async move { parsed.await.unwrap_or(0) };
+//!             assert!(options.r#await && await_timeout == 5);
+//!             future
+//!         } catch error {
+//!             panic!("{}", error)
+//!         }
+//!     }
+//! }
+//! ```
+//! Closures and the async blocks nested in them are skipped too:
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{future::Future, num::ParseIntError};
+//! async fn fetch(text: &str) -> Result<u32, ParseIntError> {
+//!     text.parse()
+//! }
+//!
+//! fn spawn_all(texts: &[&'static str]) -> Vec<impl Future<Output = u32>> {
+//!     catch! {
+//!         try {
+//!             let count: usize = "2".parse()?;
+//!             let fetch_or_zero = |text: &'static str| async move { fetch(text).await.unwrap_or(0) };
+//!             assert_eq!(count, 2);
+//!             texts
+//!                 .iter()
+//!                 .take(count)
+//!                 .map(|&text| async move { fetch(text).await.unwrap_or(0) + async { 1 }.await })
+//!                 .collect::<Vec<_>>()
+//!         } catch error {
+//!             vec![]
+//!         }
+//!     }
+//! }
+//! assert_eq!(spawn_all(&["1", "2", "3"]).len(), 2);
+//! ```
+//! The guess can be overridden by writing `async` or `sync` right before the
+//! try block:
+//! ```rust
+//! # use try_catch::catch;
+//! async fn parse(text: &'static str) -> i32 {
+//!     let parsed = async move { text.parse::<i32>() };
+//!     catch! {
+//!         try async {
+//!             parsed.await?
+//!         } catch error {
+//!             0
+//!         }
+//!     }
+//! }
+//!
+//! fn count_awaits() -> i32 {
+//!     catch! {
+//!         try sync {
+//!             let await_count = "3".parse::<i32>()?;
+//!             await_count
+//!         } catch error {
+//!             0
+//!         }
+//!     }
+//! }
+//! assert_eq!(count_awaits(), 3);
+//! ```
+//!
+//! The async block is awaited right away, so it borrows from the enclosing
+//! function like a hand-written one, including across `.await`s:
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! struct Store {
+//!     prefix: String,
+//! }
+//!
+//! impl Store {
+//!     async fn get(&self, key: &str) -> Result<u32, ParseIntError> {
+//!         key.trim_start_matches(self.prefix.as_str()).parse()
+//!     }
+//!
+//!     async fn sum(&self, keys: &[&str], total: &mut u32) -> usize {
+//!         let mut read = 0;
+//!         catch! {
+//!             try {
+//!                 for key in keys {
+//!                     *total += self.get(key).await?;
+//!                     read += 1;
+//!                 }
+//!             } catch error {}
+//!         }
+//!         read
+//!     }
+//! }
+//! ```
+//!
+//! Only the try block is moved into an async block: the catch arms run in the
+//! enclosing function, so they can `.await` whenever it is async, whether or
+//! not the try block does.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{future::Future, num::ParseIntError};
+//! struct Transaction {
+//!     rolled_back: bool,
+//! }
+//!
+//! impl Transaction {
+//!     async fn rollback(&mut self) {
+//!         self.rolled_back = true;
+//!     }
+//! }
+//!
+//! async fn insert(tx: &mut Transaction, text: &str) -> i32 {
+//!     catch! {
+//!         try {
+//!             text.parse::<i32>()?
+//!         } catch error: ParseIntError {
+//!             tx.rollback().await;
+//!             -1
+//!         } catch error {
+//!             0
+//!         }
+//!     }
+//! }
+//! # fn block_on<F: Future>(future: F) -> F::Output {
+//! #     use std::{sync::Arc, task::{Context, Poll, Wake}};
+//! #     struct Noop;
+//! #     impl Wake for Noop { fn wake(self: Arc<Self>) {} }
+//! #     let waker = Arc::new(Noop).into();
+//! #     let mut future = Box::pin(future);
+//! #     loop {
+//! #         if let Poll::Ready(output) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+//! #             return output;
+//! #         }
+//! #     }
+//! # }
+//! let mut tx = Transaction { rolled_back: false };
+//! assert_eq!(block_on(insert(&mut tx, "one")), -1);
+//! assert!(tx.rolled_back);
+//! ```
+//!
+//! Writing `lazy` before `try` makes the expression a future instead: the try
+//! block and the catch arms only run when it is polled, so it can be stored,
+//! spawned or joined with other futures. Its output is what the expression
+//! would have evaluated to.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::future::Future;
+//! fn parse(text: &'static str) -> impl Future<Output = i32> {
+//!     catch! {
+//!         move lazy try {
+//!             async { () }.await;
+//!             text.parse::<i32>()?
+//!         } catch error {
+//!             0
+//!         }
+//!     }
+//! }
+//!
+//! let futures: Vec<_> = ["1", "two"].iter().map(|text| parse(text)).collect();
+//! ```
+//!
+//! The boxed error is never held across an await of the arm that handles it,
+//! nor across the sleep of a retry policy, so a future using `catch!` is
+//! `Send` whenever the code written in it is, and can be spawned on a
+//! multi-threaded runtime such as with `tokio::spawn`.
+//! ```rust
+//! # use try_catch::{catch, Backoff};
+//! # use std::{future::Future, io, thread, time::Duration};
+//! async fn log(message: String) {}
+//! async fn sleep(duration: Duration) {}
+//!
+//! async fn read(text: &str) -> io::Result<i32> {
+//!     text.parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "not a number"))
+//! }
+//!
+//! async fn handle(text: String) -> i32 {
+//!     catch! {
+//!         try retry with Backoff::new(Duration::from_millis(1), 2) sleep sleep {
+//!             read(&text).await?
+//!         } or {
+//!             read("0").await?
+//!         } catch error: io::Error {
+//!             log(error.to_string()).await;
+//!             -1
+//!         } catch _ {
+//!             log(String::from("unexpected")).await;
+//!             -2
+//!         }
+//!     }
+//! }
+//! # fn block_on<F: Future>(future: F) -> F::Output {
+//! #     use std::{sync::Arc, task::{Context, Poll, Wake}};
+//! #     struct Noop;
+//! #     impl Wake for Noop { fn wake(self: Arc<Self>) {} }
+//! #     let waker = Arc::new(Noop).into();
+//! #     let mut future = Box::pin(future);
+//! #     loop {
+//! #         if let Poll::Ready(output) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+//! #             return output;
+//! #         }
+//! #     }
+//! # }
+//! // `thread::spawn` requires the future to be `Send`, as `tokio::spawn` does.
+//! let future = handle(String::from("seven"));
+//! assert_eq!(thread::spawn(move || block_on(future)).join().unwrap(), 0);
+//! ```
+//!
+//! Arms that keep the error across an await, because they catch it by
+//! reference, borrow it with `deep` or use it after awaiting, and `cleanup`
+//! blocks that await, need an error that is `Send` and `Sync`, as carried by
+//! `send`.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::{future::Future, io, thread};
+//! async fn log(error: &io::Error) {}
+//!
+//! async fn read() -> io::Result<i32> {
+//!     Err(io::Error::new(io::ErrorKind::TimedOut, "too slow"))
+//! }
+//!
+//! async fn handle() -> i32 {
+//!     catch! {
+//!         send try {
+//!             read().await?
+//!         } catch &error: io::Error {
+//!             log(error).await;
+//!         } catch _ {
+//!             0
+//!         }
+//!     }
+//! }
+//! # fn block_on<F: Future>(future: F) -> F::Output {
+//! #     use std::{sync::Arc, task::{Context, Poll, Wake}};
+//! #     struct Noop;
+//! #     impl Wake for Noop { fn wake(self: Arc<Self>) {} }
+//! #     let waker = Arc::new(Noop).into();
+//! #     let mut future = Box::pin(future);
+//! #     loop {
+//! #         if let Poll::Ready(output) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+//! #             return output;
+//! #         }
+//! #     }
+//! # }
+//! assert_eq!(thread::spawn(|| block_on(handle())).join().unwrap(), 0);
+//! ```
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # use std::{future::Future, io, thread};
+//! # async fn log(error: &io::Error) {}
+//! # async fn read() -> io::Result<i32> { Ok(1) }
+//! # fn spawn<F: Future + Send + 'static>(future: F) {}
+//! // error: future cannot be sent between threads safely
+//! spawn(async {
+//!     catch! {
+//!         try {
+//!             read().await?
+//!         } catch &error: io::Error {
+//!             log(error).await;
+//!         } catch _ {
+//!             0
+//!         }
+//!     }
+//! });
+//! ```
+//!
+//! ## Streams
+//! With the `futures` feature, `catch_stream!` runs the catch arms over each
+//! item of a `Stream` of results, and evaluates to a stream of the items that
+//! come out of them. It takes the stream like `match` takes a `Result`. An arm
+//! replaces the error with an item of its own, or skips it with `continue`.
+//! An error that no arm handles, or that an arm rethrows or throws, is yielded
+//! as an `Err` item, which ends the stream. The documentation of
+//! `CatchStream`, the stream it evaluates to, has an example.
+//!
+//! The arms capture what they use by `move`, as the closure given to
+//! `StreamExt::map` would, and cannot `.await` or `return`. The stream is
+//! pinned in a box by `CatchStream`, so streams that are not `Unpin` can be
+//! given as well.
+//!
+//! ## Control flow
+//! Synchronous try blocks run in place, so `return`, `break` and `continue`
+//! behave as if the block was written without the macro: `return` leaves the
+//! enclosing function, and `break` and `continue` target the loops around the
+//! macro.
+//! ```rust
+//! # use try_catch::catch;
+//! fn first_even(texts: &[&str]) -> Option<i32> {
+//!     for text in texts {
+//!         catch! {
+//!             try {
+//!                 let number: i32 = text.parse()?;
+//!                 if number % 2 == 0 {
+//!                     return Some(number);
+//!                 }
+//!             } catch error {}
+//!         }
+//!     }
+//!     None
+//! }
+//! assert_eq!(first_even(&["x", "3", "4", "6"]), Some(4));
+//!
+//! fn log(texts: &[&str], lines: &mut Vec<String>) {
+//!     for text in texts {
+//!         catch! {
+//!             try {
+//!                 if text.is_empty() {
+//!                     return;
+//!                 }
+//!                 lines.push(text.to_string());
+//!             } catch error {}
+//!         }
+//!     }
+//! }
+//! let mut lines = vec![];
+//! log(&["a", "", "b"], &mut lines);
+//! assert_eq!(lines, ["a"]);
+//!
+//! let mut sum = 0;
+//! 'rows: for row in [["1", "2"], ["", "3"], ["4", "5"], ["x", "6"]] {
+//!     for cell in row {
+//!         catch! {
+//!             try {
+//!                 if cell.is_empty() {
+//!                     continue 'rows;
+//!                 }
+//!                 sum += cell.parse::<i32>()?;
+//!             } catch error {
+//!                 break 'rows;
+//!             }
+//!         }
+//!     }
+//! }
+//! assert_eq!(sum, 12);
+//! ```
+//! `cleanup` and `finally` still run when the try block is left this way, from
+//! drop guards. Like closures, they then borrow what they use for as long as
+//! the expression runs, and cannot `.await`.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::cell::Cell;
+//! fn first_word(text: &str, closed: &Cell<u32>) -> String {
+//!     let words: Vec<String> = catch! {
+//!         try {
+//!             if let Some(word) = text.split(' ').next() {
+//!                 return word.to_string();
+//!             }
+//!             vec![]
+//!         } catch error {
+//!             vec![]
+//!         } finally {
+//!             closed.set(closed.get() + 1);
+//!         }
+//!     };
+//!     words.concat()
+//! }
+//! let closed = Cell::new(0);
+//! assert_eq!(first_word("hello world", &closed), "hello");
+//! assert_eq!(closed.get(), 1);
+//! ```
+//!
+//! Async try blocks, try blocks with a `catch panic` arm, and `move` or
+//! retried try blocks run inside a closure or an async block instead. So do
+//! try blocks calling macros other than those of the standard library, since
+//! they may hide a `return` or a `?` of their own, with the exception of
+//! [`throw!`], [`bail!`] and [`ensure!`], which raise their error from the try
+//! block. A `return`, `break` or `continue` would only leave the closure, so it
+//! is rejected in those try blocks:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! # macro_rules! log { ($($message:tt)*) => {} }
+//! fn parse(text: &str) -> i32 {
+//!     catch! {
+//!         try {
+//!             log!("parsing {}", text);
+//!             if text.is_empty() {
+//!                 // error: `return` cannot leave this try block, which runs in a closure, since it calls `log!`
+//!                 return 0;
+//!             }
+//!             text.parse()?
+//!         } catch error {
+//!             -1
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! ## Captures
+//! Try blocks that run in place use the variables around them as the same code
+//! would without the macro: reading or mutating a variable only borrows it for
+//! as long as the block runs, and moving a field out of a struct leaves the
+//! rest of it usable afterwards.
+//! ```rust
+//! # use try_catch::catch;
+//! struct Request {
+//!     body: String,
+//!     retries: u32,
+//! }
+//!
+//! let ports = vec!["80", "http"];
+//! let mut parsed = vec![];
+//! let request = Request { body: "42".to_string(), retries: 3 };
+//! let body: String = catch! {
+//!     try {
+//!         for port in &ports {
+//!             parsed.push(port.parse::<u16>()?);
+//!         }
+//!         request.body
+//!     } catch error {
+//!         String::new()
+//!     }
+//! };
+//! assert_eq!(ports.len(), 2);
+//! assert_eq!(parsed, [80]);
+//! assert_eq!(request.retries, 3);
+//! assert_eq!(body, "");
+//! ```
+//!
+//! A try block that does not run in place captures variables by reference
+//! whenever it can, like an ordinary closure. Writing `move` before `try` turns
+//! it into a `move` closure (and a `move` async block for async try blocks),
+//! which takes ownership of the captured variables:
+//! ```rust
+//! # use try_catch::catch;
+//! async fn parse(text: String) -> i32 {
+//!     catch! {
+//!         move try {
+//!             let text = async { text }.await;
+//!             text.parse::<i32>()?
+//!         } catch error {
+//!             0
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! ## Else
+//! An `else` clause placed right after the try block only runs when the try
+//! block succeeded. It binds the success value and its result becomes the value
+//! of the expression. Errors raised inside the `else` block are not covered by
+//! the catch arms.
+//! ```rust
+//! # use try_catch::catch;
+//! let doubled: i32 = catch! {
+//!     try {
+//!         "21".parse::<i32>()?
+//!     } else number {
+//!         number * 2
+//!     } catch error {
+//!         0
+//!     }
+//! };
+//! assert_eq!(doubled, 42);
+//! ```
+//!
+//! Unlike Python's `try`/`except`/`else`, the `else` clause comes before the
+//! arms, and errors are caught with `catch` rather than `except`.
+//! ```compile_fail
+//! # use try_catch::catch;
+//! // error: `else` must come right after the try block, before the catch arms
+//! let doubled: i32 = catch! {
+//!     try {
+//!         "21".parse::<i32>()?
+//!     } catch error {
+//!         0
+//!     } else number {
+//!         number * 2
+//!     }
+//! };
+//! ```
+//! ```compile_fail
+//! # use try_catch::catch;
+//! // error: errors are caught with `catch`, not `except`
+//! let number: i32 = catch! {
+//!     try {
+//!         "21".parse::<i32>()?
+//!     } except error {
+//!         0
+//!     }
+//! };
+//! ```
+//!
+//! ## Retry
+//! `try retry N` re-runs the try block up to `N` more times while it keeps failing.
+//! The catch arms only see the error of the last attempt. Since the block runs
+//! more than once, the variables it captures are borrowed rather than consumed.
+//! ```rust
+//! # use try_catch::catch;
+//! let mut attempts = 0;
+//! let number: i32 = catch! {
+//!     try retry 3 {
+//!         attempts += 1;
+//!         if attempts < 3 { "flaky" } else { "3" }.parse::<i32>()?
+//!     } catch error {
+//!         0
+//!     }
+//! };
+//! assert_eq!(number, 3);
+//! assert_eq!(attempts, 3);
+//! ```
+//! A catch arm can also carry its own `retry N`. The try block is then only
+//! re-run when the error would be handled by that arm, and the arm body runs
+//! once its attempts are exhausted. Other arms behave as usual.
+//! ```rust
+//! # use try_catch::catch;
+//! # use std::num::ParseIntError;
+//! let mut attempts = 0;
+//! let number: i32 = catch! {
+//!     try {
+//!         attempts += 1;
+//!         "flaky".parse::<i32>()?
+//!     } catch error: ParseIntError retry 2 {
+//!         -1
+//!     } catch error {
+//!         0
+//!     }
+//! };
+//! assert_eq!(number, -1);
+//! assert_eq!(attempts, 3);
+//! ```
+//! For backoff, `try retry with policy` takes any value implementing
+//! [`RetryPolicy`], such as [`Backoff`], which can inspect each error and
+//! decide how long to wait before the next attempt, if at all. Synchronous try
+//! blocks wait with `std::thread::sleep`; async ones need a sleep function
+//! returning a future, given with `sleep`:
+//! ```rust
+//! # use try_catch::{catch, Backoff};
+//! # use std::time::Duration;
+//! async fn sleep(duration: Duration) {
+//!     // e.g. tokio::time::sleep(duration).await
+//! }
+//!
+//! async fn fetch() -> Result<i32, std::num::ParseIntError> {
+//!     "flaky".parse()
+//! }
+//!
+//! async fn fetch_or_zero() -> i32 {
+//!     catch! {
+//!         try retry with Backoff::new(Duration::from_millis(10), 5) sleep sleep {
+//!             fetch().await?
+//!         } catch error {
+//!             0
+//!         }
+//!     }
+//! }
+//! ```
+//! With the `tokio` feature enabled, `tokio::time::sleep` is used when no
+//! `sleep` function is given, through this crate, so the crate invoking
+//! `catch!` needs no dependency on `tokio` of its own.
+//!
+//! `retry 0` is rejected at compile time:
+//! ```compile_fail
+//! # use try_catch::catch;
+//! catch! {
+//!     try retry 0 {
+//!         "3".parse::<i32>()?
+//!     } catch error {
+//!         0
+//!     }
+//! };
+//! ```
+//!
+//! ## Wrapping in macros
+//! `catch!` can be invoked from `macro_rules!`, with the blocks, patterns,
+//! types and expressions of the invocation passed in as fragments. This makes
+//! it possible to share a set of arms between functions:
+//! ```rust
+//! use std::num::ParseIntError;
+//! use try_catch::catch;
+//!
+//! macro_rules! or_default {
+//!     ($body:block $(, $error:pat => $value:block)* $(; finally $finally:block)?) => {
+//!         catch! {
+//!             try $body
+//!             $(catch $error: ParseIntError $value)*
+//!             catch _ => Default::default(),
+//!             $(finally $finally)?
+//!         }
+//!     };
+//! }
+//!
+//! macro_rules! parsed {
+//!     ($text:expr) => {
+//!         catch! {
+//!             try $text.parse::<i32>()?;
+//!             catch _: ParseIntError => -1,
+//!         }
+//!     };
+//! }
+//!
+//! let mut attempts = 0;
+//! let number: i32 = or_default!({ "ten".parse::<i32>()? }; finally { attempts += 1 });
+//! assert_eq!(number, 0);
+//! let number: i32 = or_default!({ "ten".parse::<i32>()? }, error => { error.to_string().len() as i32 });
+//! assert_eq!(number, 29);
+//! assert_eq!(attempts, 1);
+//! let number: Result<i32, Box<dyn std::error::Error>> = parsed!("ten");
+//! assert_eq!(number.unwrap(), -1);
+//! ```
+//! `throw` cannot be used in a `$body:block`, since the block is parsed by the
+//! compiler before `catch!` sees it. Pass the try block as `$body:tt` instead.
+//! [`try_or!`] is such a macro, for a try block that falls back on a default
+//! value whatever the error.
+//!
+//! ## Functions
+//! `#[try_catch(..)]` runs the body of a function as a try block, with the
+//! arms given to the attribute, and returns the value of the `catch!`: without
+//! a wildcard arm, a `Result` holding the unhandled errors. Anything written
+//! before `try` in a `catch!`, such as `propagate` or `into Type`, can be
+//! written before the arms. It applies to async functions, generic functions
+//! and methods alike:
+//! ```rust
+//! # use try_catch::try_catch;
+//! # use std::{error::Error, io, num::ParseIntError};
+//! #[derive(Debug, PartialEq)]
+//! struct Config {
+//!     port: u16,
+//! }
+//!
+//! #[try_catch(catch _: ParseIntError => Config { port: 80 }, catch _ => Config { port: 0 })]
+//! fn load(text: &str) -> Config {
+//!     if text.is_empty() {
+//!         Err(io::Error::from(io::ErrorKind::NotFound))?;
+//!     }
+//!     Config { port: text.parse()? }
+//! }
+//!
+//! struct Server {
+//!     config: Config,
+//! }
+//!
+//! impl Server {
+//!     #[try_catch(catch error: ParseIntError {
+//!         eprintln!("keeping port {}: {}", self.config.port, error);
+//!     })]
+//!     fn reconfigure(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+//!         self.config = Config { port: text.parse()? };
+//!     }
+//! }
+//!
+//! assert_eq!(load("8080"), Config { port: 8080 });
+//! assert_eq!(load("http"), Config { port: 80 });
+//! assert_eq!(load(""), Config { port: 0 });
+//!
+//! let mut server = Server { config: load("8080") };
+//! server.reconfigure("http").unwrap();
+//! assert_eq!(server.config.port, 8080);
+//! ```
+//! The function has to be valid Rust before the attribute sees it, so `throw`
+//! cannot be used in it; raise errors with `Err(error)?` or [`throw!`] instead.
+//!
+//! On an impl block, the arms apply to every method, each keeping its own
+//! `async` and return type. `#[try_catch(skip)]` opts a method out, and a
+//! method with a `#[try_catch(..)]` of its own is only wrapped in its own arms:
+//! ```rust
+//! # use try_catch::try_catch;
+//! # use std::{collections::HashMap, fmt, num::ParseIntError};
+//! #[derive(Debug, PartialEq)]
+//! enum StoreError {
+//!     Missing,
+//!     Corrupt,
+//!     Other(String),
+//! }
+//! # impl fmt::Display for StoreError {
+//! #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{:?}", self) }
+//! # }
+//! # impl std::error::Error for StoreError {}
+//!
+//! struct Store {
+//!     rows: HashMap<u32, String>,
+//! }
+//!
+//! #[try_catch(
+//!     into StoreError
+//!     catch error: StoreError => error,
+//!     catch _: ParseIntError => StoreError::Corrupt,
+//!     catch error => StoreError::Other(error.to_string()),
+//! )]
+//! impl Store {
+//!     #[try_catch(skip)]
+//!     fn new() -> Self {
+//!         Store { rows: HashMap::new() }
+//!     }
+//!
+//!     fn count(&self, id: u32) -> Result<u64, StoreError> {
+//!         let row = self.rows.get(&id).ok_or(StoreError::Missing)?;
+//!         row.parse()?
+//!     }
+//!
+//!     fn import(&mut self, path: &str) -> Result<(), StoreError> {
+//!         let text = std::fs::read_to_string(path)?;
+//!         self.rows.insert(0, text);
+//!     }
+//!
+//!     #[try_catch(catch _ => 0)]
+//!     fn count_or_zero(&self, id: u32) -> u64 {
+//!         self.count(id)?
+//!     }
+//! }
+//!
+//! let mut store = Store::new();
+//! store.rows.insert(1, "12".to_string());
+//! store.rows.insert(2, "twelve".to_string());
+//! assert_eq!(store.count(1), Ok(12));
+//! assert_eq!(store.count(2), Err(StoreError::Corrupt));
+//! assert_eq!(store.count(3), Err(StoreError::Missing));
+//! assert!(matches!(store.import("/missing"), Err(StoreError::Other(_))));
+//! assert_eq!(store.count_or_zero(2), 0);
+//! ```
+//!
+//! ## Declared errors
+//! `#[throws(..)]` lists the errors a function raises. The function returns a
+//! `Result` of its declared return type and a `Box<dyn Error>`, its body is the
+//! `Ok` value, as is the value of each `return`, and a `?` raising an error
+//! that is not listed is a compile error. Callers then know which typed arms
+//! to write:
+//! ```rust
+//! # use try_catch::{catch, throws};
+//! # use std::{fs, io, num::ParseIntError};
+//! #[throws(io::Error, ParseIntError)]
+//! fn port(path: &str) -> u16 {
+//!     if path.is_empty() {
+//!         return 8080;
+//!     }
+//!     fs::read_to_string(path)?.trim().parse()?
+//! }
+//!
+//! assert_eq!(port("").unwrap(), 8080);
+//! let port: u16 = catch! {
+//!     try {
+//!         port("/missing/port")?
+//!     }
+//!     catch _: io::Error => 80,
+//!     catch _: ParseIntError => 0,
+//!     catch _ => unreachable!(),
+//! };
+//! assert_eq!(port, 80);
+//! ```
+//! ```rust,compile_fail
+//! # use try_catch::throws;
+//! # use std::io;
+//! #[throws(io::Error)]
+//! fn port(text: &str) -> u16 {
+//!     // error: `?` couldn't raise `ParseIntError`, which is not one of the errors listed by `#[throws]`
+//!     text.parse()?
+//! }
+//! ```
+//!
+//! With `enum Name { .. }`, the function returns a `Result<_, Name>` instead,
+//! and the enum is generated next to it, with the visibility of the function.
+//! Each variant holds one of the errors, which it converts from with `?`, and
+//! displays as. Callers match it with `enum Name` in a `catch!`, which checks
+//! that every variant is handled:
+//! ```rust
+//! # use try_catch::{catch, throws};
+//! # use std::{fs, io, num::ParseIntError};
+//! #[throws(enum PortError { Io(io::Error), Parse(ParseIntError) })]
+//! fn port(path: &str) -> u16 {
+//!     fs::read_to_string(path)?.trim().parse()?
+//! }
+//!
+//! let port: u16 = catch! {
+//!     enum PortError try {
+//!         port("/missing/port")?
+//!     }
+//!     catch PortError::Io(_) => 80,
+//!     catch PortError::Parse(_) => 0,
+//! };
+//! assert_eq!(port, 80);
+//! ```
+//! The enum is an item, so the enum form only applies to free functions, and
+//! the function cannot use generic parameters in it. Like `#[try_catch]`, the
+//! attribute does not see into macros: write `return Ok(value)` in a `catch!`
+//! of the function, and note that [`throw!`] and [`bail!`] convert their
+//! error with `From` without checking that it is listed.
+//!
+//! ## Main functions
+//! `#[catch::main(..)]` runs `main` as a try block whose arms give the exit
+//! code of the process. An error that no arm handles is printed to stderr, as
+//! `error: ..` followed by a `caused by: ..` line for each of its sources, and
+//! exits with code 1. `main` itself returns `()`, and exits with code 0 when
+//! the body completes:
+//! ```rust
+//! # use std::{io, num::ParseIntError};
+//! use try_catch::catch;
+//!
+//! #[catch::main(
+//!     catch error: ParseIntError {
+//!         eprintln!("invalid port: {}", error);
+//!         2
+//!     }
+//!     catch _: io::Error => 74,
+//! )]
+//! fn main() {
+//!     let port: u16 = std::env::var("PORT").as_deref().unwrap_or("8080").parse()?;
+//!     println!("listening on {}", port);
+//! }
+//! ```
+//! Async mains work the same, with `#[catch::main]` written above the
+//! attribute of the runtime, such as `#[tokio::main]`, so that it sees the
+//! body of the async function.
+//! ```rust,compile_fail
+//! # use try_catch::catch;
+//! #[catch::main]
+//! fn main() -> Result<(), std::io::Error> {
+//!     // error: `#[catch::main]` functions return `()`, the arms give the exit code instead
+//!     Ok(())
+//! }
+//! ```
+//!
+//! ## Tests
+//! `#[catch::test(..)]`, stacked with `#[test]`, runs a test as a try block and
+//! fails it with the same report as `#[catch::main]` when no arm handles an
+//! error, rather than the `Debug` output of a test returning `Result`. The
+//! report is the message of the panic, so `#[should_panic(expected = "..")]`
+//! can match it. With `expect = Type`, the test only passes if it raises a
+//! `Type`, and fails if it raises another error or none at all:
+//! ```rust
+//! # use std::{fs, io, panic};
+//! use try_catch::catch;
+//!
+//! # /*
+//! #[test]
+//! # */
+//! #[catch::test]
+//! fn reads_config() {
+//!     fs::read_to_string("/missing/config.toml")?;
+//! }
+//!
+//! # /*
+//! #[test]
+//! # */
+//! #[catch::test(expect = io::Error)]
+//! fn rejects_missing_config() {
+//!     fs::read_to_string("/missing/config.toml")?;
+//! }
+//!
+//! # /*
+//! #[test]
+//! # */
+//! #[catch::test(expect = io::Error)]
+//! fn parses_port_without_io() {
+//!     let port: u16 = "8080".parse()?;
+//!     assert_eq!(port, 8080);
+//! }
+//!
+//! # panic::set_hook(Box::new(|_| {}));
+//! let failure = panic::catch_unwind(reads_config).unwrap_err();
+//! let report = failure.downcast_ref::<String>().unwrap();
+//! assert!(report.starts_with("error: No such file or directory"));
+//!
+//! rejects_missing_config();
+//!
+//! let failure = panic::catch_unwind(parses_port_without_io).unwrap_err();
+//! let report = failure.downcast_ref::<String>().unwrap();
+//! assert_eq!(report, "the test succeeded, expected it to fail with `std::io::error::Error`");
+//! ```
+//! Catch arms can be given as well, to check the errors a test expects, and
+//! async tests work like async mains, with `#[catch::test]` written above
+//! `#[tokio::test]`.
+//!
+//! ## Hygiene
+//! The expansion refers to everything it uses by its full path, so `catch!`
+//! works in modules without the prelude and next to items that shadow prelude
+//! names such as `Box` or `Ok`.
+//! ```rust
+//! mod parse {
+//!     #![no_implicit_prelude]
+//!     use ::std::{io, num::ParseIntError};
+//!     use ::try_catch::{catch, catch_opt};
+//!
+//!     pub fn number(text: &str) -> i32 {
+//!         catch! {
+//!             try {
+//!                 text.parse::<i32>()?
+//!             } catch error: ParseIntError | io::Error {
+//!                 -1
+//!             } catch error {
+//!                 0
+//!             }
+//!         }
+//!     }
+//!
+//!     pub fn retried(text: &str) -> ::std::option::Option<i32> {
+//!         catch_opt! {
+//!             try retry 2 {
+//!                 text.parse::<i32>()?
+//!             } catch &error: ParseIntError {
+//!             } catch error: io::Error {
+//!                 rethrow;
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! struct Box;
+//! struct Ok;
+//!
+//! fn main() {
+//!     assert_eq!(parse::number("ten"), -1);
+//!     assert_eq!(parse::retried("ten"), None);
+//! }
+//! ```
+//!
+//! The try block, guards, arm bodies and the `finally`, `cleanup` and `else`
+//! blocks are passed through with the spans they were written with, so
+//! editors resolve, complete and hover code in them as if it were written
+//! outside the macro. `?` and `throw` are rewritten, but the code they expand
+//! to is located at the `?` and the `throw` keyword. Only the identifiers the
+//! macro introduces, which are prefixed with `__try_catch`, are hidden from the
+//! surrounding code.
+#![cfg_attr(not(feature = "std"), no_std)]
 
-mod prelude;
-
-use crate::prelude::*;
-use proc_macro2::Span;
-
-use quote::ToTokens;
-use syn::{parse::Parse, spanned::Spanned};
-
-#[proc_macro]
-pub fn catch(input: TokenStream) -> TokenStream {
-    let try_catch = parse_macro_input!(input as TryCatch);
-
-    template(try_catch).into()
-}
-
-struct TryCatch {
-    try_block: ExprBlock,
-    catches: Vec<Catch>,
-    is_async: bool,
-}
-struct Catch {
-    error: Ident,
-    err_type: Option<Type>,
-    block: ExprBlock,
-}
-
-fn parse_block(input: &parse::ParseStream) -> Result<ExprBlock> {
-    let out = input.parse().map(|block| match block {
-        Expr::Block(block) => Ok(block),
-        span => Err(Error::new(span.span(), "Expected a block `{ /* ... */ }`.")),
-    })??;
-    Ok(out)
-}
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
-impl Parse for TryCatch {
-    fn parse(input: parse::ParseStream) -> Result<Self> {
-        let _try_kw: Token![try] = input.parse()?;
-        let try_block = parse_block(&input)?;
-        let ts = try_block.to_token_stream();
-        let is_async = is_async(ts);
-        let mut catches = vec![];
-        while let Ok(catch) = input.parse() {
-            catches.push(catch)
-        }
+#[cfg(feature = "alloc")]
+mod cast;
+mod defer;
+#[cfg(feature = "alloc")]
+mod downcast;
+#[cfg(feature = "alloc")]
+mod error;
+#[cfg(feature = "std")]
+mod poison;
+#[cfg(feature = "std")]
+mod report;
+#[cfg(feature = "alloc")]
+mod resource;
+#[cfg(feature = "alloc")]
+mod retry;
+#[cfg(feature = "futures")]
+mod stream;
+#[cfg(feature = "alloc")]
+mod timeout;
+#[cfg(feature = "std")]
+mod trace;
 
-        Ok(TryCatch {
-            try_block,
-            catches,
-            is_async,
-        })
-    }
-}
+#[cfg(feature = "alloc")]
+pub use cast::ErrorCast;
+pub use defer::Deferred;
+#[cfg(feature = "alloc")]
+pub use error::{
+    ContextError, DisplayError, HandlerError, Located, MessageError, NoneError, Panicked, Rethrown,
+    WhileHandling,
+};
+#[cfg(feature = "std")]
+pub use poison::Poisoned;
+#[cfg(feature = "alloc")]
+pub use resource::{Closing, Resource};
+#[cfg(feature = "alloc")]
+pub use retry::{Backoff, RetryPolicy};
+#[cfg(feature = "futures")]
+pub use stream::CatchStream;
+#[cfg(feature = "alloc")]
+pub use timeout::Elapsed;
+#[cfg(feature = "std")]
+pub use trace::{backtrace, Traced};
+#[cfg(feature = "futures")]
+pub use try_catch_macros::catch_stream;
+pub use try_catch_macros::{catch, catch_expr, catch_opt, throws, try_catch};
 
-impl Parse for Catch {
-    fn parse(input: parse::ParseStream) -> Result<Self> {
-        let catch_kw: Ident = input.parse()?;
-        if catch_kw != "catch" {
-            return Err(Error::new(catch_kw.span(), "Expected `catch`"));
-        }
-        let error: Ident = input.parse()?;
-        let err_type = if input.peek(Token![:]) {
-            eprintln!("Yes colon\n\n\n\n");
-            let _colon: Token![:] = input.parse()?;
-            Some(input.parse()?)
-        } else {
-            eprintln!("No colon\n\n\n\n");
-            None
-        };
-        let block = parse_block(&input)?;
-        Ok(Catch {
-            error,
-            err_type,
-            block,
-        })
-    }
+/// Attributes that run a function as the try block of a `catch!`, reachable
+/// through the same `use try_catch::catch;` as the macro.
+#[cfg(feature = "std")]
+pub mod catch {
+    pub use try_catch_macros::{main, test};
 }
-use syn::ExprBlock;
 
-fn template(try_catch: TryCatch) -> TokenStream2 {
-    let try_block = try_catch.try_block;
-    let result = Ident::new("__try_catch_block", Span::mixed_site());
-    let result_err = Ident::new("__try_catch_error", Span::mixed_site());
-
-    let mut template = if try_catch.is_async {
-        quote![
-            let #result: ::std::result::Result<_, Box<dyn ::std::error::Error>> = (|| async {Ok(#try_block)})().await;
-        ]
-    } else {
-        quote![
-            let #result: ::std::result::Result<_, Box<dyn ::std::error::Error>> = (|| Ok(#try_block))();
-        ]
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::downcast::{
+        borrow, borrow_mut, downcast, downcast_ref, found, handled, is, matched, take_err, take_ok,
+        unbox, untraced, untraced_mut,
     };
-
-    let mut catch_template = quote!();
-    let mut warn_unused_must_use = true;
-    for catch in try_catch.catches {
-        let block = catch.block;
-        let error_name = catch.error;
-        if let Some(err_type) = catch.err_type {
-            catch_template.extend(quote![
-                _ if  #result_err.is::<#err_type>() => {
-                    let #error_name = #result_err.downcast::<#err_type>().unwrap();
-                    ::std::result::Result::Ok(#block)
-                }
-            ]);
-        } else {
-            warn_unused_must_use = false;
-            catch_template.extend(quote![
-                _ => {
-                    let #error_name = #result_err;
-                    ::std::result::Result::Ok(#block)
-                }
-            ]);
-        }
-    }
-
-    catch_template.extend(quote![
-        _ => {
-            ::std::result::Result::Err(#result_err)
-        }
-    ]);
-
-    template.extend(quote![
-        if let ::std::result::Result::Err(#result_err) = #result {
-           match () { #catch_template }
-        } else {
-            #result
-        }
-    ]);
-
-    if warn_unused_must_use {
-        quote!({#template})
-    } else {
-        quote!({#template.ok().unwrap()})
-    }
-}
-
-fn is_async(input: TokenStream2) -> bool {
-    let mut out = false;
-    for token in input {
-        match token {
-            proc_macro2::TokenTree::Ident(ident) => {
-                if ident == "await" {
-                    out = true;
-                }
-            }
-            proc_macro2::TokenTree::Group(group) => {
-                out |= is_async(group.stream());
-            }
-            _ => (),
-        }
-    }
-    out
+    pub use crate::error::{
+        assert_catchable, declared, fallible, find_source, propagate, raise, unhandled, Propagating,
+    };
+    #[cfg(feature = "std")]
+    pub use crate::report::{exit_report, panic_report, unexpected_success};
+    pub use crate::timeout::timeout;
+    #[cfg(feature = "std")]
+    pub use crate::trace::Trace;
+    #[cfg(feature = "tokio")]
+    pub use ::tokio;
+    pub use alloc::boxed::Box;
+    pub use alloc::format;
 }
@@ -0,0 +1,29 @@
+use core::error::Error;
+use core::fmt;
+use std::sync::PoisonError;
+
+/// The error of a lock that a thread panicked while holding, without the guard
+/// that [`PoisonError`] borrows from the lock.
+///
+/// A `PoisonError` cannot be raised with `?` in a try block, since errors are
+/// boxed and the guard it holds borrows the lock. Converting it with
+/// `lock().map_err(Poisoned::from)?` drops the guard, and typed arms can then
+/// catch it as `catch error: Poisoned`.
+#[derive(Debug)]
+pub struct Poisoned {
+    _private: (),
+}
+
+impl<T> From<PoisonError<T>> for Poisoned {
+    fn from(_: PoisonError<T>) -> Self {
+        Poisoned { _private: () }
+    }
+}
+
+impl fmt::Display for Poisoned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("poisoned lock: another task failed inside")
+    }
+}
+
+impl Error for Poisoned {}
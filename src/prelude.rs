@@ -1,4 +0,0 @@
-pub use proc_macro::{TokenStream, *};
-pub use proc_macro2::TokenStream as TokenStream2;
-pub use syn::{*, Ident};
-pub use quote::quote;
\ No newline at end of file
@@ -0,0 +1,49 @@
+use core::error::Error;
+use core::fmt;
+use std::backtrace::BacktraceStatus;
+
+use crate::downcast::untraced;
+use crate::error::Sources;
+
+/// Displays an error as `error: ..`, followed by a `caused by: ..` line for
+/// each of its sources, and by its backtrace when one was captured.
+pub struct Report<'a>(pub &'a (dyn Error + 'static));
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let error = untraced(self.0);
+        write!(f, "error: {}{}", error, Sources(error))?;
+        match crate::backtrace(self.0) {
+            Some(backtrace) if backtrace.status() == BacktraceStatus::Captured => {
+                write!(f, "\n\nstack backtrace:\n{}", backtrace)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Fails a test of `#[catch::test]` with the report of an error that no arm
+/// handled.
+#[cold]
+#[track_caller]
+pub fn panic_report(error: &(dyn Error + 'static)) -> ! {
+    panic!("{}", Report(error))
+}
+
+/// Fails a test of `#[catch::test(expect = ..)]` that raised no error.
+#[cold]
+#[track_caller]
+pub fn unexpected_success(expected: &[&str]) -> ! {
+    panic!(
+        "the test succeeded, expected it to fail with `{}`",
+        expected.join("` or `")
+    )
+}
+
+/// Prints the report of an error that no arm of `#[catch::main]` handled, and
+/// returns the exit code of the process.
+#[cold]
+pub fn exit_report(error: &(dyn Error + 'static)) -> i32 {
+    std::eprintln!("{}", Report(error));
+    1
+}
@@ -0,0 +1,40 @@
+use core::ops::{Deref, DerefMut};
+
+/// A resource that has to be released explicitly rather than by dropping it.
+pub trait Resource {
+    fn close(&mut self);
+}
+
+/// Calls [`Resource::close`] on the wrapped resource when dropped.
+///
+/// Meant for the resources of a `try with` clause, which are dropped as soon as
+/// the try block is left, but it works in any scope. It dereferences to the
+/// wrapped resource.
+#[derive(Debug)]
+pub struct Closing<R: Resource>(R);
+
+impl<R: Resource> Closing<R> {
+    pub fn new(resource: R) -> Self {
+        Closing(resource)
+    }
+}
+
+impl<R: Resource> Deref for Closing<R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        &self.0
+    }
+}
+
+impl<R: Resource> DerefMut for Closing<R> {
+    fn deref_mut(&mut self) -> &mut R {
+        &mut self.0
+    }
+}
+
+impl<R: Resource> Drop for Closing<R> {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
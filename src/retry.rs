@@ -0,0 +1,97 @@
+use core::error::Error;
+use core::time::Duration;
+
+/// Decides whether a failed try block is run again, and how long to wait first.
+///
+/// Used with `try retry with policy { ... }`. Closures taking the attempt number
+/// and the error implement this trait, which makes one-off policies easy to write:
+/// ```rust
+/// # use try_catch::catch;
+/// # use std::{error::Error, time::Duration};
+/// let mut attempts = 0;
+/// let number: i32 = catch! {
+///     try retry with |attempt: u32, _: &dyn Error| (attempt < 3).then(|| Duration::from_millis(1)) {
+///         attempts += 1;
+///         "flaky".parse::<i32>()?
+///     } catch error {
+///         0
+///     }
+/// };
+/// assert_eq!(number, 0);
+/// assert_eq!(attempts, 3);
+/// ```
+pub trait RetryPolicy {
+    /// Called after the `attempt`-th failure of the try block (starting at 1) with the error
+    /// it produced. Returns the time to wait before the next attempt, or `None` to stop
+    /// retrying and hand the error to the catch arms.
+    fn delay(&mut self, attempt: u32, error: &dyn Error) -> Option<Duration>;
+}
+
+impl<F> RetryPolicy for F
+where
+    F: FnMut(u32, &dyn Error) -> Option<Duration>,
+{
+    fn delay(&mut self, attempt: u32, error: &dyn Error) -> Option<Duration> {
+        self(attempt, error)
+    }
+}
+
+/// Exponential backoff: waits `initial` after the first failure and doubles the
+/// delay after every further failure, giving up after `retries` retries.
+/// ```rust
+/// # use try_catch::{catch, Backoff};
+/// # use std::time::Duration;
+/// let number: i32 = catch! {
+///     try retry with Backoff::new(Duration::from_millis(1), 3).max_delay(Duration::from_millis(2)) {
+///         "flaky".parse::<i32>()?
+///     } catch error {
+///         0
+///     }
+/// };
+/// assert_eq!(number, 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max_delay: Option<Duration>,
+    retries: u32,
+}
+
+impl Backoff {
+    /// Waits `initial` before the first retry and stops after `retries` retries.
+    /// ```rust
+    /// # use try_catch::{Backoff, RetryPolicy};
+    /// # use std::{io, time::Duration};
+    /// let mut backoff = Backoff::new(Duration::from_millis(10), 2);
+    /// let error = io::Error::other("unreachable");
+    /// assert_eq!(backoff.delay(1, &error), Some(Duration::from_millis(10)));
+    /// assert_eq!(backoff.delay(2, &error), Some(Duration::from_millis(20)));
+    /// assert_eq!(backoff.delay(3, &error), None);
+    /// ```
+    pub fn new(initial: Duration, retries: u32) -> Self {
+        Backoff {
+            initial,
+            max_delay: None,
+            retries,
+        }
+    }
+
+    /// Caps the delay between two attempts.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = Some(max_delay);
+        self
+    }
+}
+
+impl RetryPolicy for Backoff {
+    fn delay(&mut self, attempt: u32, _: &dyn Error) -> Option<Duration> {
+        if attempt > self.retries {
+            return None;
+        }
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        let delay = self.initial.saturating_mul(factor);
+        Some(self.max_delay.map_or(delay, |max| delay.min(max)))
+    }
+}
@@ -0,0 +1,119 @@
+use alloc::boxed::Box;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use futures_core::stream::{FusedStream, Stream};
+
+/// The stream of [`catch_stream!`](crate::catch_stream), which runs the catch
+/// arms over the errors of another stream.
+///
+/// The handler is called with each item, and returns `None` to skip it, or the
+/// item to yield. The stream ends after yielding an error, which is one that
+/// the arms did not handle or raised themselves. The inner stream is pinned in
+/// a box, so that streams that are not `Unpin` can be adapted as well.
+///
+/// ```rust
+/// # use try_catch::catch_stream;
+/// # use futures_core::Stream;
+/// # use std::{num::{IntErrorKind, ParseIntError}, pin::Pin, sync::Arc, task::{Context, Poll, Wake, Waker}};
+/// # struct Noop;
+/// # impl Wake for Noop {
+/// #     fn wake(self: Arc<Self>) {}
+/// # }
+/// # struct Iter<I>(I);
+/// # impl<I: Iterator + Unpin> Stream for Iter<I> {
+/// #     type Item = I::Item;
+/// #     fn poll_next(mut self: Pin<&mut Self>, _: &mut Context) -> Poll<Option<I::Item>> {
+/// #         Poll::Ready(self.0.next())
+/// #     }
+/// # }
+/// # fn collect<S: Stream + Unpin>(mut stream: S) -> Vec<S::Item> {
+/// #     let mut items = vec![];
+/// #     let waker = Waker::from(Arc::new(Noop));
+/// #     let mut context = Context::from_waker(&waker);
+/// #     while let Poll::Ready(Some(item)) = Pin::new(&mut stream).poll_next(&mut context) {
+/// #         items.push(item);
+/// #     }
+/// #     items
+/// # }
+/// let lines = Iter(vec!["1", "", "2", "three", "4"].into_iter());
+/// let numbers = catch_stream! {
+///     match Iter(lines.0.map(str::parse::<u8>)) {
+///         catch error: ParseIntError if *error.kind() == IntErrorKind::Empty => continue,
+///     }
+/// };
+/// let numbers = collect(numbers);
+/// assert_eq!(numbers.len(), 3);
+/// assert_eq!(*numbers[0].as_ref().unwrap(), 1);
+/// assert_eq!(*numbers[1].as_ref().unwrap(), 2);
+/// assert!(numbers[2].as_ref().unwrap_err().is::<ParseIntError>());
+/// ```
+#[must_use = "streams do nothing unless polled"]
+pub struct CatchStream<S, F> {
+    stream: Pin<Box<S>>,
+    handler: F,
+    ended: bool,
+}
+
+impl<S, F> CatchStream<S, F> {
+    pub fn new<T, C>(stream: S, handler: F) -> Self
+    where
+        S: Stream,
+        F: FnMut(S::Item) -> Option<Result<T, C>>,
+    {
+        CatchStream {
+            stream: Box::pin(stream),
+            handler,
+            ended: false,
+        }
+    }
+}
+
+// The inner stream is pinned in its box, and the handler is never pinned.
+impl<S, F> Unpin for CatchStream<S, F> {}
+
+impl<S, F, T, C> Stream for CatchStream<S, F>
+where
+    S: Stream,
+    F: FnMut(S::Item) -> Option<Result<T, C>>,
+{
+    type Item = Result<T, C>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while !this.ended {
+            let item = match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(item)) => item,
+                Poll::Ready(None) => break,
+                Poll::Pending => return Poll::Pending,
+            };
+            match (this.handler)(item) {
+                Some(Ok(value)) => return Poll::Ready(Some(Ok(value))),
+                Some(Err(error)) => {
+                    this.ended = true;
+                    return Poll::Ready(Some(Err(error)));
+                }
+                None => {}
+            }
+        }
+        this.ended = true;
+        Poll::Ready(None)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.ended {
+            return (0, Some(0));
+        }
+        // Any item may be skipped.
+        (0, self.stream.size_hint().1)
+    }
+}
+
+impl<S, F, T, C> FusedStream for CatchStream<S, F>
+where
+    S: Stream,
+    F: FnMut(S::Item) -> Option<Result<T, C>>,
+{
+    fn is_terminated(&self) -> bool {
+        self.ended
+    }
+}
@@ -0,0 +1,47 @@
+use core::error::Error;
+use core::fmt;
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::Poll;
+use core::time::Duration;
+
+/// The error raised when the try block of a `try timeout` clause misses its deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed {
+    duration: Duration,
+}
+
+impl Elapsed {
+    /// The deadline that was missed.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "deadline of {:?} elapsed", self.duration)
+    }
+}
+
+impl Error for Elapsed {}
+
+/// Runs `future` until it completes or `sleep` does, whichever comes first.
+pub async fn timeout<F, S>(duration: Duration, future: F, sleep: S) -> Result<F::Output, Elapsed>
+where
+    F: Future,
+    S: Future,
+{
+    let mut future = pin!(future);
+    let mut sleep = pin!(sleep);
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        match sleep.as_mut().poll(cx) {
+            Poll::Ready(_) => Poll::Ready(Err(Elapsed { duration })),
+            Poll::Pending => Poll::Pending,
+        }
+    })
+    .await
+}
@@ -0,0 +1,107 @@
+use alloc::boxed::Box;
+use core::error::Error;
+use core::fmt;
+use std::backtrace::Backtrace;
+
+/// An error that reached a wildcard arm or was left unhandled by the catch
+/// arms, with the backtrace of the `catch!` it went through.
+///
+/// Errors are only wrapped by `catch!` invocations marked `traced`, and the
+/// backtrace is only captured when `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` ask
+/// for it, as with [`Backtrace::capture`]. The original error stays reachable through
+/// [`Error::source`], and typed catch arms match it through the wrapper.
+#[derive(Debug)]
+pub struct Traced<E: ?Sized = dyn Error> {
+    pub(crate) error: Box<E>,
+    backtrace: Backtrace,
+}
+
+impl<E: ?Sized> Traced<E> {
+    pub(crate) fn new(error: Box<E>) -> Self {
+        Traced {
+            error,
+            backtrace: Backtrace::capture(),
+        }
+    }
+
+    pub fn backtrace(&self) -> &Backtrace {
+        &self.backtrace
+    }
+
+    /// Returns the original error, discarding the backtrace.
+    pub fn into_source(self) -> Box<E> {
+        self.error
+    }
+}
+
+impl<E: ?Sized + fmt::Display> fmt::Display for Traced<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl Error for Traced {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.error)
+    }
+}
+
+impl Error for Traced<dyn Error + Send + Sync> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.error)
+    }
+}
+
+/// Returns the backtrace of the first [`Traced`] error among `error` and its
+/// sources, if any.
+pub fn backtrace<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a Backtrace> {
+    let mut error = Some(error);
+    while let Some(current) = error {
+        if let Some(traced) = current.downcast_ref::<Traced>() {
+            return Some(traced.backtrace());
+        }
+        if let Some(traced) = current.downcast_ref::<Traced<dyn Error + Send + Sync>>() {
+            return Some(traced.backtrace());
+        }
+        error = current.source();
+    }
+    None
+}
+
+/// Wraps the errors that leave the catch arms of a `traced` invocation, through
+/// a wildcard arm or unhandled, in a [`Traced`] error.
+pub trait Trace {
+    fn trace(self) -> Self;
+}
+
+impl Trace for Box<dyn Error> {
+    #[inline]
+    fn trace(self) -> Self {
+        if is_traced(&*self) {
+            return self;
+        }
+        Box::new(Traced::new(self))
+    }
+}
+
+impl Trace for Box<dyn Error + Send + Sync> {
+    #[inline]
+    fn trace(self) -> Self {
+        if is_traced(&*self) {
+            return self;
+        }
+        Box::new(Traced::new(self))
+    }
+}
+
+/// Only `Box<dyn Error>` and the box of `send` are traced.
+impl Trace for Box<dyn Error + Send> {
+    #[inline]
+    fn trace(self) -> Self {
+        self
+    }
+}
+
+fn is_traced(error: &(dyn Error + 'static)) -> bool {
+    error.is::<Traced>() || error.is::<Traced<dyn Error + Send + Sync>>()
+}
@@ -0,0 +1,31 @@
+//! Checks the `no-alloc` package on its own, where `try-catch` is built with
+//! `default-features = false`. Within the workspace, the other packages enable
+//! `alloc` and `std` for it.
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn no_alloc() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let output = Command::new(cargo)
+        .args([
+            "check",
+            "--quiet",
+            "--offline",
+            "--package",
+            "try-catch-no-alloc",
+        ])
+        .arg("--target-dir")
+        .arg(root.join("target/tests/no-alloc"))
+        .current_dir(root)
+        .env_remove("RUSTFLAGS")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr),
+    );
+}
@@ -0,0 +1,74 @@
+//! Checks that the `tokio` feature lets async try blocks retry and time out
+//! without a `sleep` function, in a package generated under `target/tests/tokio` that
+//! depends on this crate but not on `tokio`.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+const LIB: &str = r#"
+use std::time::Duration;
+use try_catch::{catch, Backoff};
+
+async fn fetch() -> Result<i32, std::num::ParseIntError> {
+    "flaky".parse()
+}
+
+pub async fn fetch_or_zero() -> i32 {
+    catch! {
+        try retry with Backoff::new(Duration::from_millis(1), 3) {
+            fetch().await?
+        } catch _ {
+            0
+        }
+    }
+}
+
+pub async fn fetch_in_time() -> i32 {
+    catch! {
+        try timeout Duration::from_secs(1) {
+            fetch().await?
+        } catch _ {
+            0
+        }
+    }
+}
+"#;
+
+#[test]
+fn tokio() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let package = root.join("target/tests/tokio");
+    fs::create_dir_all(package.join("src")).unwrap();
+    let manifest = format!(
+        "[package]\n\
+         name = \"try-catch-tokio\"\n\
+         version = \"0.0.0\"\n\
+         edition = \"2018\"\n\
+         publish = false\n\
+         \n\
+         [workspace]\n\
+         \n\
+         [dependencies]\n\
+         try-catch = {{ path = {:?}, features = [\"tokio\"] }}\n",
+        root.display().to_string(),
+    );
+    fs::write(package.join("Cargo.toml"), manifest).unwrap();
+    fs::write(package.join("src/lib.rs"), LIB).unwrap();
+    // The versions this crate is built with, which are already downloaded.
+    if let Ok(lock) = fs::read(root.join("Cargo.lock")) {
+        fs::write(package.join("Cargo.lock"), lock).unwrap();
+    }
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let output = Command::new(cargo)
+        .args(["check", "--quiet", "--offline"])
+        .current_dir(&package)
+        .env_remove("RUSTFLAGS")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr),
+    );
+}
@@ -0,0 +1,147 @@
+//! Checks the diagnostics of the invocations in `tests/ui` that must not
+//! compile against the `.stderr` file next to each of them.
+//!
+//! Each file is compiled as a binary of a package generated under
+//! `target/tests/ui`, which depends on this crate. Run with `UI=overwrite` to
+//! write the `.stderr` files from the current diagnostics instead.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde_json::Value;
+
+#[test]
+fn ui() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = root.join("tests/ui");
+    let mut cases: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "rs"))
+        .collect();
+    cases.sort();
+    let package = root.join("target/tests/ui");
+    write_package(root, &package, &cases);
+    let overwrite = std::env::var("UI").is_ok_and(|ui| ui == "overwrite");
+    let mut failures = vec![];
+    for case in &cases {
+        let name = case.file_stem().unwrap().to_str().unwrap();
+        let actual = normalize(&diagnostics(&package, name), root);
+        let expected_path = case.with_extension("stderr");
+        if overwrite {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&expected_path).unwrap_or_default();
+        if actual.is_empty() {
+            failures.push(format!("{} compiled, but it is expected to fail", name));
+        } else if actual != expected {
+            failures.push(format!(
+                "{} failed with other diagnostics than {}:\n{}",
+                name,
+                expected_path.display(),
+                actual,
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "\n\n{}", failures.join("\n\n"));
+}
+
+/// Writes the package compiling each of `cases` as a binary.
+fn write_package(root: &Path, package: &Path, cases: &[PathBuf]) {
+    fs::create_dir_all(package).unwrap();
+    let mut manifest = format!(
+        "[package]\n\
+         name = \"try-catch-ui\"\n\
+         version = \"0.0.0\"\n\
+         edition = \"2018\"\n\
+         publish = false\n\
+         \n\
+         [workspace]\n\
+         \n\
+         [dependencies]\n\
+         try-catch = {{ path = {:?} }}\n",
+        root.display().to_string(),
+    );
+    for case in cases {
+        let name = case.file_stem().unwrap().to_str().unwrap();
+        manifest += &format!(
+            "\n[[bin]]\nname = {:?}\npath = {:?}\n",
+            name,
+            case.display().to_string(),
+        );
+    }
+    fs::write(package.join("Cargo.toml"), manifest).unwrap();
+    // The versions this crate is built with, which are already downloaded.
+    if let Ok(lock) = fs::read(root.join("Cargo.lock")) {
+        fs::write(package.join("Cargo.lock"), lock).unwrap();
+    }
+}
+
+/// The rendered diagnostics of compiling the binary `name` of `package`.
+fn diagnostics(package: &Path, name: &str) -> String {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let output = Command::new(cargo)
+        .args([
+            "check",
+            "--quiet",
+            "--offline",
+            "--message-format=json",
+            "--bin",
+            name,
+        ])
+        .current_dir(package)
+        .env_remove("RUSTFLAGS")
+        .output()
+        .unwrap();
+    let mut rendered = String::new();
+    for line in String::from_utf8(output.stdout).unwrap().lines() {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(_) => continue,
+        };
+        // Warnings such as unused variables are left out, so that the cases
+        // need not be written around them.
+        if message["reason"] != "compiler-message"
+            || message["target"]["name"] != name
+            || message["message"]["level"] != "error"
+        {
+            continue;
+        }
+        if let Some(text) = message["message"]["rendered"].as_str() {
+            rendered += text;
+        }
+    }
+    if rendered.is_empty() && !output.status.success() {
+        // Cargo itself failed, as when the package cannot be resolved.
+        panic!("{}", String::from_utf8_lossy(&output.stderr));
+    }
+    rendered
+}
+
+/// Replaces the paths that depend on the machine with `$DIR`, the directory
+/// of the cases, `$CRATE`, the root of this crate, and `$RUST`, the standard
+/// library.
+fn normalize(diagnostics: &str, root: &Path) -> String {
+    let root = root.display().to_string();
+    diagnostics
+        .lines()
+        // Written with a hash of the build, when a type is too long to print.
+        .filter(|line| !line.contains("the full name for the type has been written to"))
+        .filter(|line| !line.contains("consider using `--verbose` to print the full type name"))
+        .map(|line| {
+            let line = line
+                .replace(&format!("{}/tests/ui/", root), "$DIR/")
+                .replace(&root, "$CRATE");
+            match line.find("/rustc/") {
+                Some(start) => {
+                    let library = line[start..].find("/library/").unwrap_or(0);
+                    format!("{}$RUST{}", &line[..start], &line[start + library..])
+                }
+                None => line,
+            }
+        })
+        .map(|line| line.trim_end().to_string() + "\n")
+        .collect()
+}
@@ -0,0 +1,13 @@
+use try_catch::catch;
+use std::io;
+
+fn main() {
+    let number: i32 = catch! {
+        try {
+            "ten".parse::<i32>()?
+        }
+        catch error: io::Error => 0,
+        catch error: io::Error if error.kind() == io::ErrorKind::NotFound => 1,
+        catch error => 2,
+    };
+}
@@ -0,0 +1,14 @@
+use try_catch::catch;
+use std::{io, num::ParseIntError};
+
+fn main() {
+    let number: i32 = catch! {
+        try {
+            "ten".parse::<i32>()?
+        }
+        catch error: ParseIntError => 1,
+        catch error: io::Error => 2,
+        catch error: io::Error if error.kind() == => 3,
+        catch error => 4,
+    };
+}
@@ -0,0 +1,14 @@
+use try_catch::catch;
+use std::{io, num::ParseIntError};
+
+fn main() {
+    let number: i32 = catch! {
+        try {
+            "ten".parse::<i32>()?
+        } catch error: ParseIntError {
+            error.to_string()
+        } catch error {
+            0
+        }
+    };
+}
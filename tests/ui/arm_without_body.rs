@@ -0,0 +1,12 @@
+use try_catch::catch;
+use std::num::ParseIntError;
+
+fn main() {
+    let number: i32 = catch! {
+        try {
+            "ten".parse::<i32>()?
+        }
+        catch error: ParseIntError
+        catch error => 3,
+    };
+}
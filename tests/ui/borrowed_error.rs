@@ -0,0 +1,26 @@
+use try_catch::catch;
+use std::fmt;
+
+#[derive(Debug)]
+struct ParseError<'a>(&'a str);
+
+impl fmt::Display for ParseError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "unexpected `{}`", self.0) }
+}
+
+impl std::error::Error for ParseError<'_> {}
+
+fn parse(input: &str) -> Result<u16, ParseError<'_>> {
+    input.parse().map_err(|_| ParseError(input))
+}
+
+fn port(input: &str) -> u16 {
+    catch! {
+        try {
+            parse(input)?
+        }
+        catch _ => 8080,
+    }
+}
+
+fn main() {}
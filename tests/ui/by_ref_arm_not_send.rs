@@ -0,0 +1,22 @@
+use try_catch::catch;
+use std::{future::Future, io};
+
+async fn log(error: &io::Error) {}
+
+async fn read() -> io::Result<i32> { Ok(1) }
+
+fn spawn<F: Future + Send + 'static>(future: F) {}
+
+fn main() {
+    spawn(async {
+        catch! {
+            try {
+                read().await?
+            } catch &error: io::Error {
+                log(error).await;
+            } catch _ {
+                0
+            }
+        }
+    });
+}
@@ -0,0 +1,11 @@
+use try_catch::catch;
+
+fn main() {
+    let number: i32 = catch! {
+        try {
+            "ten".parse::<i32>()?
+        }
+        catch error: anyhow::Error => 1,
+        catch error => 0,
+    };
+}
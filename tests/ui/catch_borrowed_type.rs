@@ -0,0 +1,17 @@
+use try_catch::catch;
+use std::sync::mpsc::{channel, SendError};
+
+fn send<'a>(name: &'a str) -> Option<&'a str> {
+    let (sender, receiver) = channel::<String>();
+    drop(receiver);
+    catch! {
+        try {
+            sender.send(name.to_owned())?;
+            None
+        }
+        catch error: SendError<&'a str> => Some(error.0),
+        catch _ => None,
+    }
+}
+
+fn main() {}
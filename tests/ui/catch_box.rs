@@ -0,0 +1,12 @@
+use try_catch::catch;
+use std::error::Error;
+
+fn main() {
+    let number: i32 = catch! {
+        try {
+            "ten".parse::<i32>()?
+        }
+        catch error: Box<dyn Error> => 1,
+        catch error => 0,
+    };
+}
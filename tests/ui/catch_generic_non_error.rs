@@ -0,0 +1,15 @@
+use try_catch::catch;
+use std::fmt::Debug;
+
+fn caught<E: Debug + 'static>() -> bool {
+    catch! {
+        try {
+            "ten".parse::<i32>()?;
+            false
+        }
+        catch _: E => true,
+        catch _ => false,
+    }
+}
+
+fn main() {}
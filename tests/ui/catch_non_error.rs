@@ -0,0 +1,14 @@
+use try_catch::catch;
+
+#[derive(Debug)]
+struct Status(u16);
+
+fn main() {
+    let number: i32 = catch! {
+        try {
+            "ten".parse::<i32>()?
+        }
+        catch status: Status => status.0.into(),
+        catch error => 0,
+    };
+}
@@ -0,0 +1,13 @@
+use try_catch::catch;
+
+fn main() {
+    let doubled: i32 = catch! {
+        try {
+            "21".parse::<i32>()?
+        } catch error {
+            0
+        } else number {
+            number * 2
+        }
+    };
+}
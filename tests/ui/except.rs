@@ -0,0 +1,11 @@
+use try_catch::catch;
+
+fn main() {
+    let number: i32 = catch! {
+        try {
+            "21".parse::<i32>()?
+        } except error {
+            0
+        }
+    };
+}
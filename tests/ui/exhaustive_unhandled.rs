@@ -0,0 +1,11 @@
+use try_catch::catch;
+use std::{fs, io, num::ParseIntError};
+
+fn main() {
+    let result: Result<i32, _> = catch! {
+        exhaustive(io::Error, ParseIntError) try {
+            fs::read_to_string("/definitely/not/here")?.trim().parse::<i32>()?
+        }
+        catch error: io::Error => 0,
+    };
+}
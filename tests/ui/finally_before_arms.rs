@@ -0,0 +1,13 @@
+use try_catch::catch;
+
+fn main() {
+    let number: i32 = catch! {
+        try {
+            "ten".parse::<i32>()?
+        } finally {
+            println!("done")
+        } catch error {
+            0
+        }
+    };
+}
@@ -0,0 +1,11 @@
+use try_catch::catch;
+use std::io;
+
+fn main() {
+    let result: Result<i32, _> = catch! {
+        send located try {
+            "ten".parse::<i32>()?
+        }
+        catch error: io::Error => 0,
+    };
+}
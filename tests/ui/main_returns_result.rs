@@ -0,0 +1,6 @@
+use try_catch::catch;
+
+#[catch::main]
+fn main() -> Result<(), std::io::Error> {
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use try_catch::catch;
+use std::fmt;
+
+struct Status(u16);
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "status {}", self.0) }
+}
+
+fn fetch() -> Result<String, Status> {
+    Err(Status(404))
+}
+
+fn main() {
+    let page = catch! {
+        try {
+            fetch()?
+        }
+        catch _ => String::new(),
+    };
+}
@@ -0,0 +1,19 @@
+use try_catch::catch;
+
+macro_rules! log { ($($message:tt)*) => {} }
+
+fn parse(text: &str) -> i32 {
+    catch! {
+        try {
+            log!("parsing {}", text);
+            if text.is_empty() {
+                return 0;
+            }
+            text.parse()?
+        } catch error {
+            -1
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use try_catch::catch;
+
+fn main() {
+    let number: i32 = catch! {
+        try {
+            "ten".parse::<i32>()?
+        }
+        try {
+            "eleven".parse::<i32>()?
+        }
+        catch error => 2,
+    };
+}
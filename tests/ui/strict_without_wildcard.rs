@@ -0,0 +1,11 @@
+use try_catch::catch;
+use std::num::ParseIntError;
+
+fn main() {
+    let number = catch! {
+        strict try {
+            "ten".parse::<i32>()?
+        }
+        catch error: ParseIntError => 0,
+    };
+}
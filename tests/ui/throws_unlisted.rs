@@ -0,0 +1,9 @@
+use std::io;
+use try_catch::throws;
+
+#[throws(io::Error)]
+fn port(text: &str) -> u16 {
+    text.parse()?
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+#![deny(unused_variables)]
+
+use try_catch::catch;
+use std::num::ParseIntError;
+
+fn main() {
+    let strict = false;
+    let number: i32 = catch! {
+        try {
+            "ten".parse::<i32>()?
+        }
+        catch error: ParseIntError if !strict => 1,
+        catch _ => 2,
+    };
+    // Used by the guard alone, which is not reported.
+    let number: i32 = catch! {
+        try {
+            "ten".parse::<i32>()?
+        }
+        catch error: ParseIntError if error.to_string().is_empty() => number,
+        catch _ => 2,
+    };
+    let _ = number;
+}